@@ -0,0 +1,93 @@
+//! # Packet Head Adjustment
+//!
+//! ## Purpose
+//!
+//! This file implements `adjust_head`, which moves the start of the packet buffer of a
+//! descriptor within its UMEM frame. It lets a received packet grow at the front to
+//! prepend encapsulation headers in place before forwarding it, or a transmitted packet
+//! be written at any offset of its frame.
+//!
+//! ## How it works
+//!
+//! The descriptor address is resolved to a plain UMEM offset, shifted by the requested
+//! delta and written back. For `_RX` the end of the packet stays in place, so the length
+//! shrinks or grows accordingly. For `_TX` the length is set later by `peek`, which checks
+//! it against the room left in the frame. In both cases the buffer must stay within its
+//! frame; a TX buffer also keeps room for the TX metadata area in front of it.
+//!
+//! ## Main components
+//!
+//! - `impl Socket<t>`: Provides `adjust_head` for both directions.
+
+#![allow(private_interfaces)]
+#![allow(private_bounds)]
+#![allow(non_upper_case_globals)]
+
+use crate::socket::{_Direction, _RX, _TX, RingError, Seek_, Socket};
+
+impl<const t: _Direction> Socket<t>
+where
+    Socket<t>: Seek_<t>,
+{
+    /// Moves the start of the `index`-th available packet buffer by `delta` bytes
+    /// within its frame.
+    ///
+    /// A negative `delta` grows a received packet at the front, e.g. to prepend an
+    /// encapsulation header that is then written with `peek_mut`; a positive one
+    /// strips bytes from its front. For a `TxSocket`, it sets where the packet data
+    /// written with `peek` starts; such a packet does not report TX timestamps.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index in the range of available descriptors.
+    /// * `delta` - The number of bytes to move the start of the buffer by.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or a `RingError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidIndex` if `index` is out of the available range, and
+    /// `RingError::InvalidLength` if the buffer would leave its frame, a received packet
+    /// would get a negative length, or a TX buffer with a metadata area would lose its
+    /// 8-byte alignment.
+    pub fn adjust_head(&mut self, index: usize, delta: isize) -> Result<(), RingError> {
+        #[cfg(not(feature = "no_safety_checks"))]
+        if index >= self.available as usize {
+            return Err(RingError::InvalidIndex);
+        }
+        let head = match t {
+            _TX => self.producer,
+            _RX => self.consumer,
+        };
+        let x_head = head.wrapping_add(index as u32) & self.x_ring.mod_mask;
+        let desc = self.x_ring.desc_at(x_head);
+        let addr = self.layout.resolve(desc.addr);
+        let base = addr - addr % self.layout.frame_size as u64;
+        let metadata_len = match t {
+            _TX => self.layout.tx_metadata_len as u64,
+            _RX => 0,
+        };
+        if metadata_len != 0 && delta % 8 != 0 {
+            return Err(RingError::InvalidLength);
+        }
+        let new_addr = addr
+            .checked_add_signed(delta as i64)
+            .filter(|&new_addr| {
+                new_addr >= base + metadata_len && new_addr < base + self.layout.frame_size as u64
+            })
+            .ok_or(RingError::InvalidLength)?;
+        let len = match t {
+            _TX => desc.len,
+            _RX => (desc.len as i64)
+                .checked_sub(delta as i64)
+                .filter(|&len| len >= 0)
+                .ok_or(RingError::InvalidLength)? as u32,
+        };
+        let desc = self.x_ring.mut_desc_at(x_head);
+        desc.addr = new_addr;
+        desc.len = len;
+        Ok(())
+    }
+}
@@ -0,0 +1,556 @@
+//!
+//! # Tokio Integration
+//!
+//! This file provides `AsyncSocket`, a wrapper registering an XDP socket with the tokio
+//! reactor through `AsyncFd`, so XDP sockets can be multiplexed with other tasks. It is
+//! available with the `tokio` feature.
+//!
+//! ## How it works
+//!
+//! The socket's file descriptor is registered with the reactor. The async `seek`
+//! methods first try the regular non-blocking `seek`. If no descriptor is available,
+//! the kernel is woken up with `kick` when it needs it, and the task waits for the
+//! readiness event of the socket direction:
+//! - For transmit sockets (`_TX`), it waits for the socket to be writable, which
+//!   happens when the kernel has consumed TX descriptors. Completing frames makes no
+//!   socket writable, so when all frames are in flight no event may come at all; the
+//!   Completion ring is therefore also checked on a tokio timer, with a backoff
+//!   growing from `MIN_BACKOFF` to `MAX_BACKOFF`.
+//! - For receive sockets (`_RX`), it waits for the socket to be readable, which
+//!   happens when packets arrive in the RX ring.
+//!
+//! The readiness is cleared only when `seek` still finds nothing, so no wakeup is lost.
+//! Once `seek` succeeds, the regular `peek`/`commit` API is used through `get_mut`.
+//!
+//! Waiting for a TX completion cannot rely on readiness events, which the kernel does
+//! not raise when it completes a frame. `wait_completion` checks the Completion ring,
+//! kicks the kernel and sleeps on a tokio timer with a growing backoff until the frame
+//! comes back or the timeout expires.
+//!
+//! ## Main components
+//!
+//! - `AsyncSocket<const t:_Direction>`: The async wrapper around `Socket<t>`.
+//! - `AsyncTxSocket`, `AsyncRxSocket`: Type aliases for the TX and RX wrappers.
+//! - `AsyncBiSocket`: The wrapper around both halves of a bidirectional socket, whose
+//!   shared file descriptor is registered once for both readiness events.
+//! - `send()` and `recv()`: Async counterparts of `SendExt::send` and `seek_and_peek`.
+//! - `send_and_wait()` and `wait_completion()`: Async counterparts of
+//!   `SendExt::send_blocking_timeout` and `Socket::wait_completion`.
+//!
+
+#![allow(private_interfaces)]
+#![allow(private_bounds)]
+#![allow(non_upper_case_globals)]
+
+use crate::completion::{MAX_BACKOFF, MIN_BACKOFF, TxCompletion, timed_out};
+use crate::send::SendExt as _;
+use crate::socket::{_Direction, _RX, _TX, Commit_, RingError, Seek_, Socket};
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::time::Duration;
+use tokio::io::Interest;
+use tokio::io::unix::AsyncFd;
+
+/// An XDP socket registered with the tokio reactor.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use xdp_socket::{create_rx_socket, AsyncRxSocket};
+/// let mut rx = AsyncRxSocket::new(create_rx_socket(if_index, 0, None)?)?;
+/// loop {
+///     let packet = rx.recv().await?;
+///     // ... process the packet ...
+///     rx.get_mut().commit()?;
+/// }
+/// ```
+pub struct AsyncSocket<const t: _Direction> {
+    /// The socket registered with the reactor.
+    fd: AsyncFd<Socket<t>>,
+}
+
+/// A type alias for an async socket configured for sending packets.
+pub type AsyncTxSocket = AsyncSocket<_TX>;
+
+/// A type alias for an async socket configured for receiving packets.
+pub type AsyncRxSocket = AsyncSocket<_RX>;
+
+impl<const t: _Direction> AsyncSocket<t>
+where
+    Socket<t>: Seek_<t> + Commit_<t>,
+{
+    /// Registers the socket with the tokio reactor of the current runtime.
+    ///
+    /// The halves of a bidirectional socket share one file descriptor, which can be
+    /// registered only once: register them together with `AsyncBiSocket` instead.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `AsyncSocket`, or an `io::Error` if the
+    /// registration fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime with IO enabled.
+    pub fn new(socket: Socket<t>) -> io::Result<Self> {
+        Ok(Self {
+            fd: AsyncFd::with_interest(socket, interest(t))?,
+        })
+    }
+
+    /// Returns a shared reference to the underlying socket.
+    #[inline]
+    pub fn get_ref(&self) -> &Socket<t> {
+        self.fd.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying socket, to `peek` and `commit`
+    /// the descriptors made available by `seek`.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut Socket<t> {
+        self.fd.get_mut()
+    }
+
+    /// Deregisters the socket from the reactor and returns it.
+    pub fn into_inner(self) -> Socket<t> {
+        self.fd.into_inner()
+    }
+
+    /// Waits until at least one descriptor is available and returns the total number
+    /// of available descriptors.
+    ///
+    /// This is the async counterpart of `Socket::seek`.
+    #[inline]
+    pub async fn seek(&mut self) -> Result<usize, RingError> {
+        self.seek_n(1).await
+    }
+
+    /// Waits until descriptors are available and returns their number, like
+    /// `Socket::seek_n` once the socket is ready.
+    ///
+    /// For a TX socket with all its frames in flight, the completed frames are found
+    /// by checking the Completion ring at least every `MAX_BACKOFF`, since the kernel
+    /// signals no readiness for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The desired number of available descriptors.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of available descriptors, or a
+    /// `RingError` if the operation fails.
+    pub async fn seek_n(&mut self, count: usize) -> Result<usize, RingError> {
+        seek_ready(&mut self.fd, interest(t), count, 1, |socket| socket).await
+    }
+}
+
+impl AsyncSocket<_TX> {
+    /// Sends a packet, waiting for free frames if not enough are available.
+    ///
+    /// The packet is copied into UMEM frames, committed and the kernel is woken
+    /// up if it needs it. On a multi-buffer socket, it waits for all the frames of
+    /// the packet's chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A byte slice containing the packet payload.
+    /// * `header` - An optional byte slice for the packet header.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `RingError` on failure.
+    pub async fn send(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError> {
+        send(
+            &mut self.fd,
+            Interest::WRITABLE,
+            |socket| socket,
+            data,
+            header,
+        )
+        .await
+    }
+
+    /// Sends a packet and waits until the kernel has completed its frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - A byte slice containing the packet payload.
+    /// * `header` - An optional byte slice for the packet header.
+    /// * `timeout` - An optional timeout. If `None`, it waits indefinitely.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TxCompletion` of the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::Io` with an error of kind `TimedOut` if the frame is not
+    /// completed in time, in addition to the errors of `send`.
+    pub async fn send_and_wait(
+        &mut self,
+        data: &[u8],
+        header: Option<&[u8]>,
+        timeout: Option<Duration>,
+    ) -> Result<TxCompletion, RingError> {
+        let addr = submit(
+            &mut self.fd,
+            Interest::WRITABLE,
+            |socket| socket,
+            data,
+            header,
+        )
+        .await?;
+        wait_completion(self.get_mut(), addr, timeout).await
+    }
+
+    /// Waits until the kernel has completed the frame submitted with address `addr`.
+    ///
+    /// This is the async counterpart of `Socket::wait_completion`, with the same
+    /// caveat: a frame already reclaimed before the call is never seen, and the wait
+    /// then lasts until the timeout, or forever with `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The UMEM address of the submitted descriptor.
+    /// * `timeout` - An optional timeout. If `None`, it waits indefinitely.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TxCompletion` of the frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::Io` with an error of kind `TimedOut` if the frame is not
+    /// completed in time, and `RingError::Io` if `kick` fails.
+    pub async fn wait_completion(
+        &mut self,
+        addr: u64,
+        timeout: Option<Duration>,
+    ) -> Result<TxCompletion, RingError> {
+        wait_completion(self.get_mut(), addr, timeout).await
+    }
+}
+
+impl AsyncSocket<_RX> {
+    /// Waits for a received packet and returns its data.
+    ///
+    /// The packet must be released with `commit` on `get_mut()` once processed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a byte slice of the received packet, or a `RingError`
+    /// on failure.
+    pub async fn recv(&mut self) -> Result<&[u8], RingError> {
+        self.seek().await?;
+        self.get_mut().peek()
+    }
+}
+
+/// The two halves of a bidirectional socket, sharing one file descriptor.
+struct SocketPair {
+    tx: Socket<_TX>,
+    rx: Socket<_RX>,
+}
+
+impl AsRawFd for SocketPair {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.tx.as_raw_fd()
+    }
+}
+
+/// Both halves of a bidirectional XDP socket registered with the tokio reactor.
+///
+/// The halves returned by `create_bi_socket` share one file descriptor, which the
+/// reactor accepts only once. This wrapper registers it for both readable and
+/// writable readiness and serves both directions.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use xdp_socket::{create_bi_socket, AsyncBiSocket};
+/// let (tx, rx) = create_bi_socket(if_index, 0, None)?;
+/// let mut socket = AsyncBiSocket::new(tx, rx)?;
+/// let packet = socket.recv().await?.to_vec();
+/// socket.rx_mut().commit()?;
+/// socket.send(&packet, None).await?;
+/// ```
+pub struct AsyncBiSocket {
+    /// The socket pair registered with the reactor.
+    fd: AsyncFd<SocketPair>,
+}
+
+impl AsyncBiSocket {
+    /// Registers both halves of a bidirectional socket with the tokio reactor of the
+    /// current runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transmit half of the socket.
+    /// * `rx` - The receive half of the socket.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `AsyncBiSocket`, or an `io::Error` if the
+    /// registration fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidInput` if the halves do not belong to the
+    /// same socket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime with IO enabled.
+    pub fn new(tx: Socket<_TX>, rx: Socket<_RX>) -> io::Result<Self> {
+        if tx.as_raw_fd() != rx.as_raw_fd() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TX and RX halves belong to different sockets",
+            ));
+        }
+        Ok(Self {
+            fd: AsyncFd::with_interest(
+                SocketPair { tx, rx },
+                Interest::READABLE | Interest::WRITABLE,
+            )?,
+        })
+    }
+
+    /// Returns a shared reference to the transmit half.
+    #[inline]
+    pub fn tx(&self) -> &Socket<_TX> {
+        &self.fd.get_ref().tx
+    }
+
+    /// Returns a shared reference to the receive half.
+    #[inline]
+    pub fn rx(&self) -> &Socket<_RX> {
+        &self.fd.get_ref().rx
+    }
+
+    /// Returns a mutable reference to the transmit half.
+    #[inline]
+    pub fn tx_mut(&mut self) -> &mut Socket<_TX> {
+        &mut self.fd.get_mut().tx
+    }
+
+    /// Returns a mutable reference to the receive half, to `peek` and `commit` the
+    /// packets made available by `seek_rx`.
+    #[inline]
+    pub fn rx_mut(&mut self) -> &mut Socket<_RX> {
+        &mut self.fd.get_mut().rx
+    }
+
+    /// Deregisters the socket from the reactor and returns its halves.
+    pub fn into_inner(self) -> (Socket<_TX>, Socket<_RX>) {
+        let SocketPair { tx, rx } = self.fd.into_inner();
+        (tx, rx)
+    }
+
+    /// Waits until TX descriptors are available and returns their number, like
+    /// `AsyncSocket::seek_n` on the transmit half.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The desired number of available descriptors.
+    pub async fn seek_tx(&mut self, count: usize) -> Result<usize, RingError> {
+        seek_ready(&mut self.fd, Interest::WRITABLE, count, 1, |pair| {
+            &mut pair.tx
+        })
+        .await
+    }
+
+    /// Waits until RX descriptors are available and returns their number, like
+    /// `AsyncSocket::seek_n` on the receive half.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The desired number of available descriptors.
+    pub async fn seek_rx(&mut self, count: usize) -> Result<usize, RingError> {
+        seek_ready(&mut self.fd, Interest::READABLE, count, 1, |pair| {
+            &mut pair.rx
+        })
+        .await
+    }
+
+    /// Sends a packet on the transmit half, like `AsyncSocket::send`.
+    pub async fn send(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError> {
+        send(
+            &mut self.fd,
+            Interest::WRITABLE,
+            |pair| &mut pair.tx,
+            data,
+            header,
+        )
+        .await
+    }
+
+    /// Sends a packet on the transmit half and waits until the kernel has completed
+    /// its frame, like `AsyncSocket::send_and_wait`.
+    pub async fn send_and_wait(
+        &mut self,
+        data: &[u8],
+        header: Option<&[u8]>,
+        timeout: Option<Duration>,
+    ) -> Result<TxCompletion, RingError> {
+        let addr = submit(
+            &mut self.fd,
+            Interest::WRITABLE,
+            |pair| &mut pair.tx,
+            data,
+            header,
+        )
+        .await?;
+        wait_completion(self.tx_mut(), addr, timeout).await
+    }
+
+    /// Waits for a packet on the receive half and returns its data, like
+    /// `AsyncSocket::recv`.
+    ///
+    /// The packet must be released with `commit` on `rx_mut()` once processed.
+    pub async fn recv(&mut self) -> Result<&[u8], RingError> {
+        self.seek_rx(1).await?;
+        self.rx_mut().peek()
+    }
+}
+
+/// Waits until the socket selected by `socket` has at least `min` descriptors
+/// available and returns their number, as `Socket::seek_n(count)` reports it.
+async fn seek_ready<T: AsRawFd, const t: _Direction>(
+    fd: &mut AsyncFd<T>,
+    interest: Interest,
+    count: usize,
+    min: usize,
+    socket: fn(&mut T) -> &mut Socket<t>,
+) -> Result<usize, RingError>
+where
+    Socket<t>: Seek_<t> + Commit_<t>,
+{
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        let ready = if t == _TX {
+            // completed frames raise no readiness event, e.g. once all frames are in
+            //  flight, so the Completion ring is also checked on a timer
+            match tokio::time::timeout(backoff, fd.ready_mut(interest)).await {
+                Ok(ready) => ready,
+                Err(_) => {
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    if let Some(available) = try_seek(socket(fd.get_mut()), count, min)? {
+                        return Ok(available);
+                    }
+                    continue;
+                }
+            }
+        } else {
+            fd.ready_mut(interest).await
+        };
+        let mut guard = ready.map_err(RingError::Io)?;
+        if let Some(available) = try_seek(socket(guard.get_inner_mut()), count, min)? {
+            return Ok(available);
+        }
+        guard.clear_ready();
+    }
+}
+
+/// Seeks `count` descriptors and returns their number if at least `min` are
+/// available, or kicks the kernel and returns `None` otherwise.
+fn try_seek<const t: _Direction>(
+    socket: &mut Socket<t>,
+    count: usize,
+    min: usize,
+) -> Result<Option<usize>, RingError>
+where
+    Socket<t>: Seek_<t> + Commit_<t>,
+{
+    match socket.seek_n(count) {
+        Ok(available) if available >= min => Ok(Some(available)),
+        Ok(_) | Err(RingError::RingFull | RingError::RingEmpty) => {
+            socket.kick().map_err(RingError::Io)?;
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Waits for the frames of a packet of `len` bytes on the TX socket selected by
+/// `socket` and returns their number.
+async fn reserve<T: AsRawFd>(
+    fd: &mut AsyncFd<T>,
+    interest: Interest,
+    socket: fn(&mut T) -> &mut Socket<_TX>,
+    len: usize,
+) -> Result<usize, RingError> {
+    let count = socket(fd.get_mut()).frames_for(len);
+    seek_ready(fd, interest, count, count, socket).await?;
+    Ok(count)
+}
+
+/// Sends a packet on the TX socket selected by `socket`, waiting for its frames.
+async fn send<T: AsRawFd>(
+    fd: &mut AsyncFd<T>,
+    interest: Interest,
+    socket: fn(&mut T) -> &mut Socket<_TX>,
+    data: &[u8],
+    header: Option<&[u8]>,
+) -> Result<(), RingError> {
+    let len = data.len() + header.map_or(0, <[u8]>::len);
+    reserve(fd, interest, socket, len).await?;
+    let tx = socket(fd.get_mut());
+    tx.send(data, header)?;
+    tx.kick().map_err(RingError::Io)
+}
+
+/// Submits a packet on the TX socket selected by `socket`, waiting for its frames,
+/// and returns the address of its last frame.
+async fn submit<T: AsRawFd>(
+    fd: &mut AsyncFd<T>,
+    interest: Interest,
+    socket: fn(&mut T) -> &mut Socket<_TX>,
+    data: &[u8],
+    header: Option<&[u8]>,
+) -> Result<u64, RingError> {
+    let len = data.len() + header.map_or(0, <[u8]>::len);
+    reserve(fd, interest, socket, len).await?;
+    let tx = socket(fd.get_mut());
+    let count = tx.write_packet(0, data, header)?;
+    // a chain completes in order, its last frame comes back last
+    let addr = tx.addr_at(count - 1)?;
+    tx.commit_(count)?;
+    Ok(addr)
+}
+
+/// Waits until the kernel has completed the frame submitted with address `addr`.
+async fn wait_completion(
+    socket: &mut Socket<_TX>,
+    addr: u64,
+    timeout: Option<Duration>,
+) -> Result<TxCompletion, RingError> {
+    let wait = async {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            if let Some(completion) = socket.reclaim_until(addr) {
+                return Ok(completion);
+            }
+            socket.kick().map_err(RingError::Io)?;
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    };
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| RingError::Io(timed_out()))?,
+        None => wait.await,
+    }
+}
+
+/// Returns the readiness interest matching the socket direction.
+#[inline]
+fn interest(t: _Direction) -> Interest {
+    match t {
+        _TX => Interest::WRITABLE,
+        _RX => Interest::READABLE,
+    }
+}
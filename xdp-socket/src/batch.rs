@@ -0,0 +1,302 @@
+//! # Batch Packet Processing
+//!
+//! ## Purpose
+//!
+//! This file provides batch helpers on top of the descriptor API, so processing many
+//! packets at once does not require juggling `seek_n`, `peek_at(i)` and `commit_n`
+//! with index arithmetic.
+//!
+//! ## How it works
+//!
+//! `recv_batch` seeks up to `max` received packets and wraps them into an `RxBatch`
+//! borrowing the socket. Iterating over the batch yields the data of each packet in
+//! order and records how many packets were consumed. When the batch is dropped, the
+//! consumed packets are committed with a single Fill ring update; packets not iterated
+//! stay available for the next batch.
+//!
+//! `send_batch` and `send_batch_with` write packets into the free TX frames, reclaiming
+//! completed frames from the Completion ring when they run out. All written packets are
+//! committed with a single TX ring producer update, and the kernel is kicked once.
+//! `send_batch` peeks at every packet before writing it, so packets that do not fit are
+//! left in the caller's iterator.
+//!
+//! ## Main components
+//!
+//! - `RxBatch`: A batch of received packets, committed on drop.
+//! - `RxBatchIter`: An iterator over the packets of an `RxBatch`.
+//! - `impl Socket<_RX>`: Provides `recv_batch`.
+//! - `impl Socket<_TX>`: Provides `send_batch` and `send_batch_with`.
+
+use crate::socket::{_RX, _TX, Commit_ as _, RingError, Seek_ as _, Socket};
+use std::cell::Cell;
+use std::iter::Peekable;
+
+impl Socket<_RX> {
+    /// Returns a batch of up to `max` received packets.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// for packet in &rx.recv_batch(64)? {
+    ///     // ... process the packet ...
+    /// }
+    /// // the packets are released when the batch is dropped
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The maximum number of packets in the batch.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `RxBatch`, empty if no packet has been received.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::Io` if the wakeup syscall of a busy-polling socket fails.
+    pub fn recv_batch(&mut self, max: usize) -> Result<RxBatch<'_>, RingError> {
+        let len = match self.seek_(max) {
+            Ok(count) => count.min(max),
+            Err(RingError::RingEmpty) => 0,
+            Err(e) => return Err(e),
+        };
+        Ok(RxBatch {
+            socket: self,
+            len,
+            consumed: Cell::new(0),
+        })
+    }
+}
+
+/// A batch of received packets, created by `Socket::recv_batch`.
+///
+/// Iterate over `&batch` to read the packets. The packets yielded by the iteration
+/// are committed when the batch is dropped; on a multi-buffer socket every fragment
+/// is a separate item.
+pub struct RxBatch<'a> {
+    /// The socket the packets were received on.
+    socket: &'a mut Socket<_RX>,
+    /// The number of packets in the batch.
+    len: usize,
+    /// The number of packets yielded so far, committed on drop.
+    consumed: Cell<usize>,
+}
+
+impl RxBatch<'_> {
+    /// Returns the number of packets in the batch.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the batch holds no packet.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the packets of the batch.
+    #[inline]
+    pub fn iter(&self) -> RxBatchIter<'_> {
+        RxBatchIter {
+            batch: self,
+            index: 0,
+        }
+    }
+
+    /// Marks every packet of the batch as consumed, so all of them are committed on
+    /// drop even if they were not iterated.
+    #[inline]
+    pub fn consume_all(&self) {
+        self.consumed.set(self.len);
+    }
+}
+
+impl<'b> IntoIterator for &'b RxBatch<'_> {
+    type Item = &'b [u8];
+    type IntoIter = RxBatchIter<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Drop for RxBatch<'_> {
+    fn drop(&mut self) {
+        // the batch never holds more packets than are available
+        let _ = self.socket.commit_(self.consumed.get());
+    }
+}
+
+/// An iterator over the packets of an `RxBatch`.
+pub struct RxBatchIter<'b> {
+    /// The batch being iterated.
+    batch: &'b RxBatch<'b>,
+    /// The index of the next packet in the batch.
+    index: usize,
+}
+
+impl<'b> Iterator for RxBatchIter<'b> {
+    type Item = &'b [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self.batch;
+        if self.index == batch.len {
+            return None;
+        }
+        let socket: &'b Socket<_RX> = batch.socket;
+        let x_head = socket.consumer.wrapping_add(self.index as u32) & socket.x_ring.mod_mask;
+        self.index += 1;
+        batch.consumed.set(batch.consumed.get().max(self.index));
+        Some(
+            socket
+                .x_ring
+                .bytes_at(socket.frames, &socket.layout, x_head),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.batch.len - self.index;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for RxBatchIter<'_> {}
+
+impl Socket<_TX> {
+    /// Sends a batch of packets with a single commit and kick.
+    ///
+    /// Packets are copied into free frames in order, until all are written or no
+    /// free frame is left after reclaiming the completed ones. On a multi-buffer
+    /// socket, a packet larger than a frame spans several frames.
+    ///
+    /// Each packet is only taken out of `packets` once it is written, so the packets
+    /// that did not fit stay in the iterator for the next call.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let mut packets = messages.iter().peekable();
+    /// while packets.peek().is_some() {
+    ///     tx.send_batch(&mut packets)?;
+    ///     // ... wait for completed frames ...
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `packets` - The packets to send, in order.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of packets queued, all taken out of
+    /// `packets`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidLength` if a packet does not fit in one frame on a
+    /// socket without multi-buffer support, and `RingError::Io` if a wakeup syscall
+    /// fails. The packets written before the error are still sent, and the packet
+    /// that failed is left in `packets`.
+    pub fn send_batch<I>(&mut self, packets: &mut Peekable<I>) -> Result<usize, RingError>
+    where
+        I: Iterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let mut used = 0;
+        let mut queued = 0;
+        let result = loop {
+            let Some(packet) = packets.peek() else {
+                break Ok(());
+            };
+            let data = packet.as_ref();
+            if !self.multi_buffer && data.len() > self.layout.capacity() {
+                break Err(RingError::InvalidLength);
+            }
+            let needed = self.frames_for(data.len());
+            if (self.available as usize) < used + needed {
+                match self.seek_(used + needed) {
+                    Ok(_) | Err(RingError::RingFull) => {}
+                    Err(e) => break Err(e),
+                }
+                if (self.available as usize) < used + needed {
+                    break Ok(());
+                }
+            }
+            match self.write_packet(used, data, None) {
+                Ok(count) => used += count,
+                Err(e) => break Err(e),
+            }
+            packets.next();
+            queued += 1;
+        };
+        self.commit_batch(used)?;
+        result.map(|()| queued)
+    }
+
+    /// Sends a batch of up to `max` packets written by a closure, with a single
+    /// commit and kick.
+    ///
+    /// `write` is called with the index of the packet in the batch and the free
+    /// frame buffer to write it into, and returns the length of the packet. It
+    /// returns `0` to end the batch early; that frame is not sent.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let queued = tx.send_batch_with(peers.len(), |i, buf| {
+    ///     write_message(&peers[i], buf)
+    /// })?;
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - The maximum number of packets to send.
+    /// * `write` - Writes a packet into a frame buffer and returns its length.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of packets queued, less than `max` if fewer
+    /// free frames are available.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidLength` if `write` returns a length larger than the
+    /// buffer, and `RingError::Io` if a wakeup syscall fails. On error, no packet is
+    /// sent.
+    pub fn send_batch_with<F>(&mut self, max: usize, mut write: F) -> Result<usize, RingError>
+    where
+        F: FnMut(usize, &mut [u8]) -> usize,
+    {
+        let count = match self.seek_(max) {
+            Ok(count) => count.min(max),
+            Err(RingError::RingFull) => 0,
+            Err(e) => return Err(e),
+        };
+        let mut queued = 0;
+        while queued < count {
+            let x_head = self.producer.wrapping_add(queued as u32) & self.x_ring.mod_mask;
+            let room = self.layout.room(self.x_ring.desc_at(x_head).addr);
+            let len = write(queued, self.peek_at(queued, room)?);
+            if len == 0 {
+                break;
+            }
+            if len > room {
+                return Err(RingError::InvalidLength);
+            }
+            self.x_ring.mut_desc_at(x_head).len = len as u32;
+            queued += 1;
+        }
+        self.commit_batch(queued)?;
+        Ok(queued)
+    }
+
+    /// Commits `count` written descriptors and kicks the kernel once.
+    fn commit_batch(&mut self, count: usize) -> Result<(), RingError> {
+        if count == 0 {
+            return Ok(());
+        }
+        self.commit_(count)?;
+        self.kick().map_err(RingError::Io)
+    }
+}
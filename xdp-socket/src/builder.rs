@@ -0,0 +1,447 @@
+//! # Socket Builder
+//!
+//! ## Purpose
+//!
+//! This file provides `SocketBuilder`, which creates AF_XDP sockets from chainable
+//! settings instead of the positional arguments of the `create_*` functions. It checks
+//! the whole configuration before any kernel object is created, so a bad ring size or
+//! UMEM geometry is reported with a descriptive error instead of a failing syscall.
+//!
+//! ## How it works
+//!
+//! The builder collects the interface, given by index or by name, the queue and an
+//! `XdpConfig`, and optionally an existing `Umem` to share. `validate` resolves the
+//! interface name, the frame layout and the ring sizes the same way socket creation
+//! does, and checks the settings against each other and against the shared UMEM. The
+//! `build_*` methods then allocate a UMEM if none was given and bind the socket(s) to
+//! it.
+//!
+//! ## Main components
+//!
+//! - `SocketBuilder`: The chainable socket settings and the `build_*` methods.
+
+use crate::create::{
+    Direction, XdpConfig, bind_socket, frame_layout, if_index_by_name, ring_sizes,
+};
+use crate::send::Backpressure;
+use crate::socket::{RxSocket, TxSocket};
+use crate::umem::Umem;
+use std::io;
+use std::sync::Arc;
+
+/// A builder for AF_XDP sockets.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let tx = SocketBuilder::new(if_index)
+///     .queue(0)
+///     .frame_count(4096)
+///     .tx_ring_size(4096)
+///     .zero_copy(true)
+///     .build_tx()?;
+/// ```
+#[derive(Clone, Default)]
+pub struct SocketBuilder {
+    /// The network interface to bind to.
+    interface: Interface,
+    /// The queue index of the interface to bind to.
+    if_queue: u32,
+    /// The socket configuration.
+    config: XdpConfig,
+    /// The UMEM to share, or `None` to allocate one for the socket(s).
+    umem: Option<Arc<Umem>>,
+}
+
+/// A network interface given by index or by name.
+#[derive(Clone, Debug)]
+enum Interface {
+    /// The interface index.
+    Index(u32),
+    /// The interface name, resolved when the sockets are built.
+    Name(String),
+}
+
+impl Default for Interface {
+    fn default() -> Self {
+        Interface::Index(0)
+    }
+}
+
+impl SocketBuilder {
+    /// Creates a builder for sockets bound to queue 0 of the interface `if_index`,
+    /// with the default configuration.
+    ///
+    /// # Arguments
+    /// * `if_index` - The index of the network interface to bind to.
+    pub fn new(if_index: u32) -> Self {
+        SocketBuilder {
+            interface: Interface::Index(if_index),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a builder for sockets bound to queue 0 of the interface named
+    /// `if_name`, with the default configuration.
+    ///
+    /// The name is resolved with `if_nametoindex` when the sockets are validated.
+    ///
+    /// # Arguments
+    /// * `if_name` - The name of the network interface to bind to, e.g. `eth0`.
+    pub fn with_name(if_name: &str) -> Self {
+        SocketBuilder {
+            interface: Interface::Name(if_name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the queue index of the interface to bind to.
+    pub fn queue(mut self, if_queue: u32) -> Self {
+        self.if_queue = if_queue;
+        self
+    }
+
+    /// Replaces the whole configuration; settings applied afterwards override it.
+    pub fn config(mut self, config: XdpConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Binds the socket(s) to an existing, possibly shared, UMEM instead of
+    /// allocating one. `frame_count` is then the number of frames to reserve from
+    /// it, and must be set.
+    pub fn umem(mut self, umem: &Arc<Umem>) -> Self {
+        self.umem = Some(umem.clone());
+        self
+    }
+
+    /// Sets the UMEM frame size, see `XdpConfig::frame_size`.
+    pub fn frame_size(mut self, frame_size: usize) -> Self {
+        self.config.frame_size = Some(frame_size);
+        self
+    }
+
+    /// Sets the number of UMEM frames, see `XdpConfig::frame_count`.
+    pub fn frame_count(mut self, frame_count: usize) -> Self {
+        self.config.frame_count = Some(frame_count);
+        self
+    }
+
+    /// Sets the headroom of the UMEM frames, see `XdpConfig::headroom`.
+    pub fn headroom(mut self, headroom: usize) -> Self {
+        self.config.headroom = Some(headroom);
+        self
+    }
+
+    /// Backs the UMEM with huge pages, see `XdpConfig::huge_page`.
+    pub fn huge_page(mut self, huge_page: bool) -> Self {
+        self.config.huge_page = Some(huge_page);
+        self
+    }
+
+    /// Sets the TX metadata area length, see `XdpConfig::tx_metadata_len`.
+    pub fn tx_metadata_len(mut self, tx_metadata_len: usize) -> Self {
+        self.config.tx_metadata_len = Some(tx_metadata_len);
+        self
+    }
+
+    /// Registers the UMEM in unaligned chunk mode, see `XdpConfig::unaligned_chunks`.
+    pub fn unaligned_chunks(mut self, unaligned_chunks: bool) -> Self {
+        self.config.unaligned_chunks = Some(unaligned_chunks);
+        self
+    }
+
+    /// Sets the TX ring size, a power of two.
+    pub fn tx_ring_size(mut self, size: usize) -> Self {
+        self.config.tx_ring_size = Some(size);
+        self
+    }
+
+    /// Sets the RX ring size, a power of two.
+    pub fn rx_ring_size(mut self, size: usize) -> Self {
+        self.config.rx_ring_size = Some(size);
+        self
+    }
+
+    /// Sets the Fill ring size, a power of two.
+    pub fn fill_ring_size(mut self, size: usize) -> Self {
+        self.config.fill_ring_size = Some(size);
+        self
+    }
+
+    /// Sets the Completion ring size, a power of two.
+    pub fn completion_ring_size(mut self, size: usize) -> Self {
+        self.config.completion_ring_size = Some(size);
+        self
+    }
+
+    /// Sets the number of frames given to RX by a bidirectional socket, see
+    /// `XdpConfig::rx_frames`.
+    pub fn rx_frames(mut self, rx_frames: usize) -> Self {
+        self.config.rx_frames = Some(rx_frames);
+        self
+    }
+
+    /// Forces zero-copy (`true`) or copy (`false`) mode, see `XdpConfig::zero_copy`.
+    pub fn zero_copy(mut self, zero_copy: bool) -> Self {
+        self.config.zero_copy = Some(zero_copy);
+        self
+    }
+
+    /// Fails socket creation if the socket is not bound in zero-copy mode, see
+    /// `XdpConfig::require_zero_copy`.
+    pub fn require_zero_copy(mut self, require_zero_copy: bool) -> Self {
+        self.config.require_zero_copy = Some(require_zero_copy);
+        self
+    }
+
+    /// Enables or disables `XDP_USE_NEED_WAKEUP`, see `XdpConfig::need_wakeup`.
+    pub fn need_wakeup(mut self, need_wakeup: bool) -> Self {
+        self.config.need_wakeup = Some(need_wakeup);
+        self
+    }
+
+    /// Enables multi-buffer packets with `XDP_USE_SG`, see `XdpConfig::multi_buffer`.
+    pub fn multi_buffer(mut self, multi_buffer: bool) -> Self {
+        self.config.multi_buffer = Some(multi_buffer);
+        self
+    }
+
+    /// Enables busy polling for up to `usecs` microseconds per syscall, see
+    /// `XdpConfig::busy_poll`.
+    pub fn busy_poll(mut self, usecs: u32) -> Self {
+        self.config.busy_poll = Some(usecs);
+        self
+    }
+
+    /// Prefers busy polling over device interrupts, see `XdpConfig::prefer_busy_poll`.
+    pub fn prefer_busy_poll(mut self, prefer_busy_poll: bool) -> Self {
+        self.config.prefer_busy_poll = Some(prefer_busy_poll);
+        self
+    }
+
+    /// Sets the number of packets processed by a single busy poll, see
+    /// `XdpConfig::busy_poll_budget`.
+    pub fn busy_poll_budget(mut self, budget: u16) -> Self {
+        self.config.busy_poll_budget = Some(budget);
+        self
+    }
+
+    /// Sets what the `send` methods of the TX socket do when no frame is free, see
+    /// `XdpConfig::backpressure`.
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.config.backpressure = Some(backpressure);
+        self
+    }
+
+    /// Checks the settings for sockets of the given direction without creating them.
+    ///
+    /// # Arguments
+    /// * `direction` - The direction(s) of the sockets to build.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` of kind `NotFound` if there is no interface with the
+    /// given name, or of kind `InvalidInput` describing the first invalid
+    /// setting: a zero interface index, conflicting zero-copy settings, busy-poll
+    /// options without a busy-poll time, an invalid UMEM geometry or one differing
+    /// from the shared UMEM, a missing frame count or more frames than the shared
+    /// UMEM has left, or ring sizes that cannot hold the frames.
+    pub fn validate(&self, direction: Direction) -> io::Result<()> {
+        self.check(direction).map(|_| ())
+    }
+
+    /// Validates the settings and returns the index of the interface to bind to.
+    fn check(&self, direction: Direction) -> io::Result<u32> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let config = &self.config;
+        let if_index = match &self.interface {
+            Interface::Index(0) => {
+                return Err(invalid("Invalid interface index 0".to_string()));
+            }
+            Interface::Index(if_index) => *if_index,
+            Interface::Name(if_name) => if_index_by_name(if_name)?,
+        };
+        if config.require_zero_copy == Some(true) && config.zero_copy == Some(false) {
+            return Err(invalid(
+                "require_zero_copy conflicts with zero_copy = Some(false)".to_string(),
+            ));
+        }
+        let busy_poll = config.busy_poll.unwrap_or(0) > 0;
+        if !busy_poll
+            && (config.prefer_busy_poll == Some(true) || config.busy_poll_budget.is_some())
+        {
+            return Err(invalid(
+                "prefer_busy_poll and busy_poll_budget require a non-zero busy_poll".to_string(),
+            ));
+        }
+        let frames = match &self.umem {
+            Some(umem) => {
+                let layout = umem.layout();
+                let mismatch = |name: &str, value: Option<usize>, expected: u32| match value {
+                    Some(value) if value != expected as usize => Err(invalid(format!(
+                        "{name} {value} differs from the shared UMEM {name} {expected}"
+                    ))),
+                    _ => Ok(()),
+                };
+                mismatch("frame size", config.frame_size, layout.frame_size)?;
+                mismatch("headroom", config.headroom, layout.headroom)?;
+                mismatch(
+                    "TX metadata length",
+                    config.tx_metadata_len,
+                    layout.tx_metadata_len,
+                )?;
+                if config
+                    .unaligned_chunks
+                    .is_some_and(|u| u != layout.unaligned)
+                {
+                    return Err(invalid(
+                        "unaligned_chunks differs from the shared UMEM chunk mode".to_string(),
+                    ));
+                }
+                // the first socket would otherwise take every frame from the others
+                let frames = config.frame_count.ok_or_else(|| {
+                    invalid("frame_count is required with a shared UMEM".to_string())
+                })?;
+                let free = umem.free_frames();
+                if frames > free {
+                    return Err(invalid(format!(
+                        "frame count {frames} exceeds the {free} free frames of the shared UMEM"
+                    )));
+                }
+                frames
+            }
+            None => frame_layout(Some(config))?.frame_count as usize,
+        };
+        ring_sizes(direction, frames, Some(config))?;
+        Ok(if_index)
+    }
+
+    /// Validates the settings and creates one or two sockets.
+    ///
+    /// # Arguments
+    /// * `direction` - The desired direction(s) for the socket (`Tx`, `Rx`, or `Both`).
+    ///
+    /// # Returns
+    /// A tuple `(Option<TxSocket>, Option<RxSocket>)`. The appropriate socket(s) will be
+    /// `Some` based on the `direction`.
+    ///
+    /// # Errors
+    /// Returns the errors of `validate`, or an `io::Error` if allocating the UMEM,
+    /// mapping the rings or binding fails.
+    pub fn build(&self, direction: Direction) -> io::Result<(Option<TxSocket>, Option<RxSocket>)> {
+        let if_index = self.check(direction)?;
+        let umem = match &self.umem {
+            Some(umem) => umem.clone(),
+            None => Arc::new(Umem::new_private(Some(self.config))?),
+        };
+        bind_socket(&umem, if_index, self.if_queue, direction, Some(self.config))
+    }
+
+    /// Validates the settings and creates a `TxSocket`.
+    pub fn build_tx(&self) -> io::Result<TxSocket> {
+        let (tx, _) = self.build(Direction::Tx)?;
+        tx.ok_or_else(|| io::Error::other("Failed to create Tx socket"))
+    }
+
+    /// Validates the settings and creates an `RxSocket`.
+    pub fn build_rx(&self) -> io::Result<RxSocket> {
+        let (_, rx) = self.build(Direction::Rx)?;
+        rx.ok_or_else(|| io::Error::other("Failed to create Rx socket"))
+    }
+
+    /// Validates the settings and creates a `TxSocket` and an `RxSocket` sharing one
+    /// AF_XDP socket, like `create_bi_socket`.
+    pub fn build_pair(&self) -> io::Result<(TxSocket, RxSocket)> {
+        let (tx, rx) = self.build(Direction::Both)?;
+        Ok((
+            tx.ok_or_else(|| io::Error::other("Failed to create Tx socket"))?,
+            rx.ok_or_else(|| io::Error::other("Failed to create Rx socket"))?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::FrameLayout;
+
+    /// Returns the message of the `InvalidInput` error `validate` reports.
+    fn invalid(builder: SocketBuilder, direction: Direction) -> String {
+        let err = builder.validate(direction).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        err.to_string()
+    }
+
+    fn shared_umem() -> Arc<Umem> {
+        Arc::new(Umem::unregistered(FrameLayout {
+            frame_size: 2048,
+            frame_count: 1024,
+            headroom: 0,
+            tx_metadata_len: 0,
+            unaligned: false,
+        }))
+    }
+
+    #[test]
+    fn validate_accepts_defaults() {
+        assert!(SocketBuilder::new(1).validate(Direction::Both).is_ok());
+        let umem = shared_umem();
+        let builder = SocketBuilder::new(1).umem(&umem).frame_count(512);
+        assert!(builder.validate(Direction::Tx).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_interface_index_zero() {
+        let msg = invalid(SocketBuilder::new(0), Direction::Tx);
+        assert!(msg.contains("interface index 0"));
+        assert!(SocketBuilder::default().validate(Direction::Rx).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_conflicting_zero_copy() {
+        let builder = SocketBuilder::new(1)
+            .require_zero_copy(true)
+            .zero_copy(false);
+        assert!(invalid(builder, Direction::Tx).contains("require_zero_copy"));
+        let builder = SocketBuilder::new(1)
+            .require_zero_copy(true)
+            .zero_copy(true);
+        assert!(builder.validate(Direction::Tx).is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_busy_poll_options_without_busy_poll() {
+        let builder = SocketBuilder::new(1).prefer_busy_poll(true);
+        assert!(invalid(builder, Direction::Rx).contains("busy_poll"));
+        let builder = SocketBuilder::new(1).busy_poll_budget(16);
+        assert!(invalid(builder, Direction::Rx).contains("busy_poll"));
+        let builder = SocketBuilder::new(1)
+            .busy_poll(50)
+            .prefer_busy_poll(true)
+            .busy_poll_budget(16);
+        assert!(builder.validate(Direction::Rx).is_ok());
+    }
+
+    #[test]
+    fn validate_requires_frame_count_with_shared_umem() {
+        let umem = shared_umem();
+        let msg = invalid(SocketBuilder::new(1).umem(&umem), Direction::Both);
+        assert!(msg.contains("frame_count"));
+        let builder = SocketBuilder::new(1).umem(&umem).frame_count(2048);
+        assert!(invalid(builder, Direction::Both).contains("free frames"));
+    }
+
+    #[test]
+    fn validate_rejects_shared_umem_geometry_mismatch() {
+        let umem = shared_umem();
+        let builder = || SocketBuilder::new(1).umem(&umem).frame_count(256);
+        assert!(invalid(builder().frame_size(4096), Direction::Tx).contains("frame size"));
+        assert!(invalid(builder().headroom(64), Direction::Tx).contains("headroom"));
+        assert!(invalid(builder().tx_metadata_len(24), Direction::Tx).contains("TX metadata"));
+        assert!(
+            invalid(builder().unaligned_chunks(true), Direction::Tx).contains("unaligned_chunks")
+        );
+        // settings equal to the shared UMEM are accepted
+        assert!(builder().frame_size(2048).validate(Direction::Tx).is_ok());
+    }
+}
@@ -0,0 +1,109 @@
+//! # Busy Polling
+//!
+//! ## Purpose
+//!
+//! This file implements the busy-poll socket options of AF_XDP sockets. With busy
+//! polling, the application thread runs the driver's NAPI loop itself from its
+//! `recvfrom`/`sendto` calls instead of waiting for interrupts and softirqs, which
+//! lowers the latency of RX loops running on isolated cores.
+//!
+//! ## How it works
+//!
+//! `set_busy_poll` applies `SO_BUSY_POLL`, `SO_PREFER_BUSY_POLL` and
+//! `SO_BUSY_POLL_BUDGET` from the `XdpConfig` when the socket is bound. With preferred
+//! busy polling the kernel defers the device interrupts, so the rings only move when the
+//! application makes a syscall. A busy-polling socket therefore wakes up the kernel on
+//! every `kick`, whether or not `XDP_RING_NEED_WAKEUP` is set, and `seek` issues a
+//! `recvfrom` (RX) or `sendto` (TX) with `MSG_DONTWAIT` when it finds no new
+//! descriptors before reporting an empty ring.
+//!
+//! ## Main components
+//!
+//! - `set_busy_poll()`: Applies the busy-poll options to a raw file descriptor.
+//! - `impl Socket<t>`: Provides `is_busy_poll`.
+
+#![allow(non_upper_case_globals)]
+
+use crate::create::XdpConfig;
+use crate::socket::{_Direction, Socket};
+use std::io;
+use std::mem::size_of;
+
+impl<const t: _Direction> Socket<t> {
+    /// Returns `true` if the socket busy polls, as set with `XdpConfig::busy_poll`.
+    #[inline]
+    pub fn is_busy_poll(&self) -> bool {
+        self.busy_poll
+    }
+}
+
+/// Applies the busy-poll options of the configuration to an AF_XDP socket.
+///
+/// Options that are not configured are left at the kernel defaults. Raising the
+/// busy-poll time or budget above the `net.core.busy_read` sysctl requires
+/// `CAP_NET_ADMIN`.
+///
+/// # Arguments
+/// * `raw_fd` - The raw file descriptor of the AF_XDP socket.
+/// * `config` - Optional configuration carrying the busy-poll options.
+///
+/// # Returns
+/// A `Result` containing `true` if busy polling is enabled on the socket.
+///
+/// # Errors
+/// Returns an `io::Error` if `setsockopt` fails.
+pub fn set_busy_poll(raw_fd: libc::c_int, config: Option<&XdpConfig>) -> io::Result<bool> {
+    let busy_poll = config.and_then(|cfg| cfg.busy_poll);
+    let prefer_busy_poll = config.and_then(|cfg| cfg.prefer_busy_poll);
+    let budget = config.and_then(|cfg| cfg.busy_poll_budget);
+    if let Some(usecs) = busy_poll {
+        set_option(
+            raw_fd,
+            "SO_BUSY_POLL",
+            libc::SO_BUSY_POLL,
+            usecs as libc::c_int,
+        )?;
+    }
+    if let Some(prefer) = prefer_busy_poll {
+        set_option(
+            raw_fd,
+            "SO_PREFER_BUSY_POLL",
+            libc::SO_PREFER_BUSY_POLL,
+            prefer as libc::c_int,
+        )?;
+    }
+    if let Some(budget) = budget {
+        set_option(
+            raw_fd,
+            "SO_BUSY_POLL_BUDGET",
+            libc::SO_BUSY_POLL_BUDGET,
+            budget as libc::c_int,
+        )?;
+    }
+    Ok(busy_poll.unwrap_or(0) > 0)
+}
+
+/// Sets an integer `SOL_SOCKET` option.
+fn set_option(
+    raw_fd: libc::c_int,
+    name: &str,
+    option: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    unsafe {
+        if libc::setsockopt(
+            raw_fd,
+            libc::SOL_SOCKET,
+            option,
+            &value as *const _ as *const libc::c_void,
+            size_of::<libc::c_int>() as libc::socklen_t,
+        ) < 0
+        {
+            return Err(io::Error::other(format!(
+                "Failed to set {name}: {}",
+                io::Error::last_os_error()
+            )));
+        }
+    }
+    Ok(())
+}
@@ -18,7 +18,8 @@
 //! For `_RX`, committing a descriptor means the application has finished
 //! processing a received packet. The `commit_` function returns the UMEM frame
 //! to the kernel by placing its descriptor in the Fill Ring, making it available
-//! for receiving new packets.
+//! for receiving new packets. Frames taken out with `take_frame` are first replaced
+//! in the Fill Ring with frames from the UMEM frame pool.
 //!
 //! ## Main components
 //!
@@ -87,11 +88,13 @@ impl Commit_<_RX> for Socket<_RX> {
         if self.available < count as u32 {
             return Err(RingError::NotAvailable);
         }
+        self.refill();
         let f_ring = &mut self.u_ring;
         let x_ring = &mut self.x_ring;
         for _ in 0..(count as u32) {
             let addr = x_ring.desc_at(self.consumer & x_ring.mod_mask).addr;
-            *f_ring.mut_desc_at(self.producer & x_ring.mod_mask) = addr;
+            let addr = self.layout.fill_addr(addr);
+            *f_ring.mut_desc_at(self.producer & f_ring.mod_mask) = addr;
             self.consumer = self.consumer.wrapping_add(1);
             self.producer = self.producer.wrapping_add(1);
         }
@@ -0,0 +1,262 @@
+//! # TX Completions
+//!
+//! ## Purpose
+//!
+//! This file implements the reclaiming of transmitted frames from the Completion ring,
+//! and exposes the completed frames with their TX timestamps. Latency-sensitive senders
+//! can measure when a packet actually left the NIC by requesting a timestamp with
+//! `TxMetadata::request_timestamp`.
+//!
+//! ## How it works
+//!
+//! Every Completion ring entry is the address of a frame the kernel has sent. Reclaiming
+//! it parks the frame as a free descriptor right after the ones already available in the
+//! TX ring, exactly what `seek` does when it runs out of descriptors.
+//!
+//! With a TX metadata area configured, the kernel writes the TX timestamp into the
+//! metadata of the frame before completing it, if the driver supports it. The timestamp
+//! is read while the frame is reclaimed and the metadata requests are cleared, so a frame
+//! reused without `peek_metadata` carries no stale request. `peek_metadata` leaves a
+//! marker in the padding of the request fields, which the timestamp overwrites. Drivers
+//! without timestamp support leave the request fields and the marker in place, and the
+//! timestamp is reported as unavailable instead of garbage.
+//!
+//! `wait_completion` waits for one given frame: it reclaims completions until the
+//! address of the frame comes back, kicking the kernel in between. The kernel raises no
+//! readiness event when it completes a frame, and the socket stays writable while the
+//! TX ring has room, so the wait sleeps between checks of the Completion ring with a
+//! backoff growing from `MIN_BACKOFF` to `MAX_BACKOFF`, until the deadline.
+//!
+//! ## Main components
+//!
+//! - `TxCompletion`: A completed frame and its optional TX timestamp.
+//! - `Completions`: An iterator reclaiming completed frames.
+//! - `impl Socket<_TX>`: Provides `completions`, `addr_at` and `wait_completion`.
+
+use crate::poll::PollWaitExt as _;
+use crate::ring::XdpDesc;
+use crate::socket::{_TX, RingError, Socket};
+use crate::tx_metadata::{TxMetadata, XDP_TXMD_FLAGS_TIMESTAMP};
+use std::time::{Duration, Instant};
+use std::{io, thread};
+
+/// The first pause between two checks of the Completion ring while waiting for a frame.
+pub(crate) const MIN_BACKOFF: Duration = Duration::from_micros(10);
+/// The longest pause between two checks of the Completion ring while waiting for a
+/// frame.
+pub(crate) const MAX_BACKOFF: Duration = Duration::from_millis(1);
+
+/// A frame completed by the kernel after transmission.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TxCompletion {
+    /// The UMEM address of the completed packet, as it was submitted.
+    pub addr: u64,
+    /// The TX timestamp in nanoseconds, if one was requested with `TxMetadata` and
+    /// the driver reported it.
+    pub timestamp: Option<u64>,
+    /// The cookie attached to the frame with `set_cookie`, if any.
+    pub cookie: Option<u64>,
+}
+
+/// An iterator over the frames completed by the kernel, created by
+/// `Socket::completions`.
+///
+/// Each yielded frame is reclaimed and becomes available for sending, like with
+/// `seek`. Frames not iterated stay in the Completion ring.
+pub struct Completions<'a> {
+    /// The socket reclaiming the frames.
+    socket: &'a mut Socket<_TX>,
+    /// The Completion ring producer index read when the iterator was created.
+    producer: u32,
+}
+
+impl Iterator for Completions<'_> {
+    type Item = TxCompletion;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.socket.consumer == self.producer {
+            return None;
+        }
+        Some(self.socket.reclaim())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.producer.wrapping_sub(self.socket.consumer) as usize;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Completions<'_> {}
+
+impl Socket<_TX> {
+    /// Returns an iterator reclaiming the frames the kernel has completed so far.
+    ///
+    /// Every completed frame becomes available for sending again, and is yielded
+    /// with its TX timestamp when one was requested and reported by the driver, and
+    /// with its cookie when one was attached.
+    ///
+    /// # Returns
+    ///
+    /// A `Completions` iterator over the completed frames.
+    pub fn completions(&mut self) -> Completions<'_> {
+        let producer = self.u_ring.producer();
+        Completions {
+            socket: self,
+            producer,
+        }
+    }
+
+    /// Returns the UMEM address of the `index`-th available descriptor, the address
+    /// its frame is completed with once sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index in the range of available descriptors.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the address of the descriptor.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidIndex` if `index` is out of the available range.
+    pub fn addr_at(&self, index: usize) -> Result<u64, RingError> {
+        if index >= self.available as usize {
+            return Err(RingError::InvalidIndex);
+        }
+        let x_head = self.producer.wrapping_add(index as u32) & self.x_ring.mod_mask;
+        Ok(self.x_ring.desc_at(x_head).addr)
+    }
+
+    /// Waits until the kernel has completed the frame submitted with address `addr`.
+    ///
+    /// Frames completed before it are reclaimed along the way and become available
+    /// for sending; their timestamps are dropped and their cookies are queued for
+    /// `completed_cookies`. Once this returns, the packet has
+    /// left the socket and its frame is free again.
+    ///
+    /// The kernel does not signal completions, so the Completion ring is checked
+    /// with pauses growing up to `MAX_BACKOFF`, which bounds the added latency.
+    ///
+    /// A completion reclaimed before the call, e.g. by `seek`, `send` or
+    /// `completions`, is not seen again: if the frame was already completed and
+    /// reclaimed, the wait lasts until the timeout, or forever with `None`. Take the
+    /// address right before submitting the frame and wait without reclaiming in
+    /// between.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let addr = tx.addr_at(0)?;
+    /// tx.send(&packet, None)?;
+    /// tx.wait_completion(addr, Some(Duration::from_millis(10)))?;
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The UMEM address of the submitted descriptor. For a multi-buffer
+    ///   packet, the address of its last fragment.
+    /// * `timeout` - An optional timeout. If `None`, it blocks indefinitely.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `TxCompletion` of the frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::Io` with an error of kind `TimedOut` if the frame is not
+    /// completed before the timeout expires, and `RingError::Io` if `kick` or `poll`
+    /// fails.
+    pub fn wait_completion(
+        &mut self,
+        addr: u64,
+        timeout: Option<Duration>,
+    ) -> Result<TxCompletion, RingError> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            if let Some(completion) = self.reclaim_until(addr) {
+                return Ok(completion);
+            }
+            let left = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => Some(left),
+                    _ => return Err(RingError::Io(timed_out())),
+                },
+                None => None,
+            };
+            // kicks the kernel and reports socket errors, readiness tells nothing here
+            match self.poll_wait(Some(Duration::ZERO)) {
+                Err(err) if err.kind() != io::ErrorKind::TimedOut => {
+                    return Err(RingError::Io(err));
+                }
+                _ => {}
+            }
+            thread::sleep(left.map_or(backoff, |left| left.min(backoff)));
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// Reclaims the completed frames up to the one with address `addr`.
+    ///
+    /// # Returns
+    ///
+    /// The `TxCompletion` of the frame, or `None` if it is not completed yet.
+    pub(crate) fn reclaim_until(&mut self, addr: u64) -> Option<TxCompletion> {
+        let producer = self.u_ring.producer();
+        while self.consumer != producer {
+            let completion = self.reclaim();
+            if completion.addr == addr {
+                return Some(completion);
+            }
+            self.cookies.defer(completion.cookie);
+        }
+        None
+    }
+
+    /// Reclaims the next entry of the Completion ring into the free TX descriptors.
+    ///
+    /// The caller must ensure the Completion ring is not empty.
+    pub(crate) fn reclaim(&mut self) -> TxCompletion {
+        let c_head = self.consumer & self.u_ring.mod_mask;
+        let addr = self.u_ring.desc_at(c_head);
+        // completed frames may come from the RX side when packets are forwarded
+        let data_addr = self.layout.data_addr(addr);
+        let timestamp = self.take_timestamp(addr, data_addr);
+        let cookie = self.cookies.take(&self.layout, addr);
+        self.consumer = self.consumer.wrapping_add(1);
+        self.u_ring.update_consumer(self.consumer);
+        // free frames are parked right after the ones already available
+        let x_head = self.producer.wrapping_add(self.available) & self.x_ring.mod_mask;
+        *self.x_ring.mut_desc_at(x_head) = XdpDesc::new(data_addr, 0, 0);
+        self.available += 1;
+        TxCompletion {
+            addr,
+            timestamp,
+            cookie,
+        }
+    }
+
+    /// Reads the TX timestamp of a completed frame and clears its metadata requests.
+    fn take_timestamp(&mut self, addr: u64, data_addr: u64) -> Option<u64> {
+        let metadata_len = self.layout.tx_metadata_len as u64;
+        if metadata_len == 0 {
+            return None;
+        }
+        let offset = (data_addr - metadata_len) as usize;
+        let metadata = unsafe { &mut *(self.frames.add(offset) as *mut TxMetadata) };
+        // only frames sent from a TX descriptor have their metadata at this place
+        let requested = addr == data_addr && metadata.flags & XDP_TXMD_FLAGS_TIMESTAMP != 0;
+        let timestamp = metadata.completion_timestamp().filter(|_| requested);
+        metadata.flags = 0;
+        timestamp
+    }
+}
+
+/// Returns the error reported when a frame is not completed in time.
+pub(crate) fn timed_out() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        "Timed out waiting for TX completion",
+    )
+}
@@ -0,0 +1,257 @@
+//! # TX Completion Cookies
+//!
+//! ## Purpose
+//!
+//! This file lets the application attach a `u64` cookie, e.g. a message id or a
+//! sequence number, to a TX frame before it is committed, and get the cookie back once
+//! the kernel has completed the frame. Higher layers use it for per-message accounting,
+//! retries and flow control without tracking UMEM addresses themselves.
+//!
+//! ## How it works
+//!
+//! Cookies are stored per UMEM frame, in a table allocated with the first cookie, so
+//! sockets that never use them pay nothing. When a completed frame is reclaimed, its
+//! cookie is taken out of the table. `completions` yields it with the `TxCompletion`;
+//! frames reclaimed on the way by `seek`, `send_batch` or `wait_completion` queue their
+//! cookie instead. `completed_cookies` drains the queued cookies first and then the
+//! Completion ring.
+//!
+//! A cookie set on a free frame follows the packet when `forward` or `send_frame` put
+//! another frame in its place, so it is reported for the packet it was set for.
+//!
+//! The queue holds at most one cookie per UMEM frame, so an application that never
+//! drains it does not grow it without bound. Once it is full, the oldest cookie is
+//! dropped for every new one.
+//!
+//! ## Main components
+//!
+//! - `CompletedCookies`: An iterator over the cookies of completed frames.
+//! - `impl Socket<_TX>`: Provides `set_cookie`, `set_cookie_at` and `completed_cookies`.
+
+use crate::ring::FrameLayout;
+use crate::socket::{_TX, RingError, Socket};
+use std::collections::VecDeque;
+
+/// The cookies attached to the frames of a TX socket.
+#[derive(Default)]
+pub(crate) struct TxCookies {
+    /// The cookie of every frame, indexed by frame number; empty until a cookie is set.
+    frames: Vec<Option<u64>>,
+    /// The cookies of frames reclaimed without being yielded, oldest first.
+    completed: VecDeque<u64>,
+}
+
+impl TxCookies {
+    /// Attaches `cookie` to the frame holding `addr`.
+    pub(crate) fn set(&mut self, layout: &FrameLayout, addr: u64, cookie: u64) {
+        if self.frames.is_empty() {
+            self.frames = vec![None; layout.frame_count as usize];
+        }
+        if let Some(slot) = self.frames.get_mut(frame_index(layout, addr)) {
+            *slot = Some(cookie);
+        }
+    }
+
+    /// Takes the cookie attached to the frame holding `addr`.
+    #[inline]
+    pub(crate) fn take(&mut self, layout: &FrameLayout, addr: u64) -> Option<u64> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        self.frames
+            .get_mut(frame_index(layout, addr))
+            .and_then(Option::take)
+    }
+
+    /// Moves the cookie attached to the frame holding `from` to the frame holding
+    /// `to`, when a free TX frame is swapped for another one before it is sent.
+    #[inline]
+    pub(crate) fn replace_frame(&mut self, layout: &FrameLayout, from: u64, to: u64) {
+        if let Some(cookie) = self.take(layout, from) {
+            self.set(layout, to, cookie);
+        }
+    }
+
+    /// Queues the cookie of a frame reclaimed without being yielded, dropping the
+    /// oldest queued cookie once one is queued per frame.
+    #[inline]
+    pub(crate) fn defer(&mut self, cookie: Option<u64>) {
+        if let Some(cookie) = cookie {
+            // a cookie was set, so the table holds one slot per frame
+            if self.completed.len() >= self.frames.len() {
+                self.completed.pop_front();
+            }
+            self.completed.push_back(cookie);
+        }
+    }
+}
+
+/// Returns the number of the frame holding `addr`.
+#[inline]
+fn frame_index(layout: &FrameLayout, addr: u64) -> usize {
+    (layout.resolve(addr) / layout.frame_size as u64) as usize
+}
+
+/// An iterator over the cookies of the frames completed by the kernel, created by
+/// `Socket::completed_cookies`.
+///
+/// Frames reclaimed from the Completion ring become available for sending, like with
+/// `seek`. Completed frames without a cookie are reclaimed and skipped.
+pub struct CompletedCookies<'a> {
+    /// The socket reclaiming the frames.
+    socket: &'a mut Socket<_TX>,
+    /// The Completion ring producer index read when the iterator was created.
+    producer: u32,
+}
+
+impl Iterator for CompletedCookies<'_> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(cookie) = self.socket.cookies.completed.pop_front() {
+            return Some(cookie);
+        }
+        while self.socket.consumer != self.producer {
+            if let Some(cookie) = self.socket.reclaim().cookie {
+                return Some(cookie);
+            }
+        }
+        None
+    }
+}
+
+impl Socket<_TX> {
+    /// Attaches a cookie to the first available descriptor, returned by
+    /// `completed_cookies` once its frame is completed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// tx.seek()?;
+    /// tx.set_cookie(message.id)?;
+    /// tx.send(&message.bytes, None)?;
+    /// // ...
+    /// for id in tx.completed_cookies() {
+    ///     pending.remove(&id);
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `cookie` - The value identifying the packet.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `RingError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidIndex` if no descriptor is available.
+    #[inline]
+    pub fn set_cookie(&mut self, cookie: u64) -> Result<(), RingError> {
+        self.set_cookie_at(0, cookie)
+    }
+
+    /// Attaches a cookie to the `index`-th available descriptor.
+    ///
+    /// The cookie belongs to the frame of the descriptor until the frame is completed,
+    /// and replaces a cookie set earlier on it. For a multi-buffer packet, set it on
+    /// one fragment only, e.g. the last one, which completes last.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index in the range of available descriptors.
+    /// * `cookie` - The value identifying the packet.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or a `RingError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidIndex` if `index` is out of the available range.
+    pub fn set_cookie_at(&mut self, index: usize, cookie: u64) -> Result<(), RingError> {
+        let addr = self.addr_at(index)?;
+        self.cookies.set(&self.layout, addr, cookie);
+        Ok(())
+    }
+
+    /// Returns an iterator over the cookies of the frames completed so far, in
+    /// completion order.
+    ///
+    /// Cookies of frames already reclaimed by `seek` and the other sending methods are
+    /// yielded first; they are kept until drained, up to one per UMEM frame, after
+    /// which the oldest are dropped. The remaining completed frames are then reclaimed
+    /// from the Completion ring.
+    ///
+    /// # Returns
+    ///
+    /// A `CompletedCookies` iterator over the cookies.
+    pub fn completed_cookies(&mut self) -> CompletedCookies<'_> {
+        let producer = self.u_ring.producer();
+        CompletedCookies {
+            socket: self,
+            producer,
+        }
+    }
+
+    /// Reclaims the next entry of the Completion ring, queueing its cookie.
+    #[inline]
+    pub(crate) fn reclaim_deferred(&mut self) {
+        let completion = self.reclaim();
+        self.cookies.defer(completion.cookie);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> FrameLayout {
+        FrameLayout {
+            frame_size: 2048,
+            frame_count: 4,
+            headroom: 0,
+            tx_metadata_len: 0,
+            unaligned: false,
+        }
+    }
+
+    #[test]
+    fn cookies_are_taken_once_per_frame() {
+        let layout = layout();
+        let mut cookies = TxCookies::default();
+        assert_eq!(cookies.take(&layout, 0), None);
+        cookies.set(&layout, 2048, 7);
+        cookies.set(&layout, 3 * 2048 + 64, 8);
+        // any address within the frame finds its cookie
+        assert_eq!(cookies.take(&layout, 2048 + 100), Some(7));
+        assert_eq!(cookies.take(&layout, 2048), None);
+        assert_eq!(cookies.take(&layout, 3 * 2048), Some(8));
+    }
+
+    #[test]
+    fn cookies_follow_a_replaced_frame() {
+        let layout = layout();
+        let mut cookies = TxCookies::default();
+        cookies.replace_frame(&layout, 0, 2048);
+        assert_eq!(cookies.take(&layout, 2048), None);
+        cookies.set(&layout, 0, 9);
+        // a received frame forwarded in place of the free one, at its packet offset
+        cookies.replace_frame(&layout, 0, 2 * 2048 + 256);
+        assert_eq!(cookies.take(&layout, 0), None);
+        assert_eq!(cookies.take(&layout, 2 * 2048), Some(9));
+    }
+
+    #[test]
+    fn deferred_cookies_are_bounded_by_frame_count() {
+        let layout = layout();
+        let mut cookies = TxCookies::default();
+        cookies.set(&layout, 0, 1);
+        cookies.defer(None);
+        for cookie in 1..=6 {
+            cookies.defer(Some(cookie));
+        }
+        assert_eq!(cookies.completed, [3, 4, 5, 6]);
+    }
+}
@@ -17,16 +17,31 @@
 //!
 //! ## Main components
 //!
-//! - `create_socket()`: The core unsafe function that handles the detailed setup logic.
+//! - `create_socket()`: Creates socket(s) with their own UMEM.
+//! - `create_shared_socket()`: Creates socket(s) bound to an existing, possibly shared,
+//!   `Umem`.
+//! - `bind_socket()`: The function that handles the detailed setup logic, used by
+//!   `SocketBuilder` once the configuration is validated.
 //! - `create_tx_socket()`, `create_rx_socket()`, `create_bi_socket()`: Safe public
 //!   functions that wrap `create_socket` for specific use cases.
+//! - `create_*_socket_by_name()`: The same functions taking an interface name.
+//! - `if_index_by_name()`: A helper resolving an interface name to its index.
 //! - `setup_umem()`: A helper function to allocate and register the UMEM with the kernel.
 //! - `ring_offsets()`: A helper to query the kernel for the memory map offsets of the rings.
+//! - `frame_layout()`: A helper to build and validate the UMEM frame layout from the config.
+//! - `ring_sizes()`: A helper to resolve and validate ring sizes and the TX/RX frame split.
 //! - `XdpConfig`, `Direction`: Public structs and enums for socket configuration.
 
+use crate::builder::SocketBuilder;
+use crate::busy_poll::set_busy_poll;
 use crate::mmap::OwnedMmap;
-use crate::ring::{FRAME_COUNT, FRAME_SIZE, Ring, RingType, XdpDesc};
+use crate::options::xdp_options;
+use crate::ring::{FRAME_COUNT, FRAME_SIZE, FrameLayout, Ring, RingType, XdpDesc};
+use crate::send::Backpressure;
 use crate::socket::{Inner, RxSocket, TxSocket};
+use crate::tx_metadata::TxMetadata;
+use crate::umem::Umem;
+use std::ffi::CString;
 use std::io;
 use std::mem::size_of;
 use std::os::fd::{FromRawFd as _, OwnedFd};
@@ -34,61 +49,173 @@ use std::sync::Arc;
 
 /// Creates one or two sockets for AF_XDP packet processing.
 ///
-/// This is the core function for setting up AF_XDP sockets. It handles UMEM
-/// allocation, ring configuration, and binding to a network interface queue.
+/// This is a thin wrapper around `SocketBuilder`, which allocates a new UMEM used only
+/// by the created socket(s).
+///
+/// # Arguments
+/// * `if_index` - The index of the network interface to bind to.
+/// * `if_queue` - The queue index of the interface to bind to.
+/// * `direction` - The desired direction(s) for the socket (`Tx`, `Rx`, or `Both`).
+/// * `config` - Optional configuration for zero-copy, huge pages, etc.
+///
+/// # Returns
+/// A tuple `(Option<TxSocket>, Option<RxSocket>)`. The appropriate socket(s) will be
+/// `Some` based on the `direction`.
+pub fn create_socket(
+    if_index: u32,
+    if_queue: u32,
+    direction: Direction,
+    config: Option<XdpConfig>,
+) -> Result<(Option<TxSocket>, Option<RxSocket>), io::Error> {
+    SocketBuilder::new(if_index)
+        .queue(if_queue)
+        .config(config.unwrap_or_default())
+        .build(direction)
+}
+
+/// Creates one or two sockets using an existing, possibly shared, UMEM.
+///
+/// The first socket created with a `Umem` is bound on the socket the UMEM is
+/// registered on. Every following socket opens its own AF_XDP socket and binds with
+/// `XDP_SHARED_UMEM`, so it must use a different queue or interface. Each socket gets
+/// its own Fill and Completion rings and reserves its own frames from the UMEM, so
+/// frame addresses are valid for every socket sharing it.
+///
+/// This is a thin wrapper around `SocketBuilder::umem`.
+///
+/// # Arguments
+/// * `umem` - The UMEM to use for packet frames.
+/// * `if_index` - The index of the network interface to bind to.
+/// * `if_queue` - The queue index of the interface to bind to.
+/// * `direction` - The desired direction(s) for the socket (`Tx`, `Rx`, or `Both`).
+/// * `config` - Configuration with `frame_count`, the number of frames to reserve
+///   from the UMEM; the UMEM geometry fields must match the UMEM if set.
+///
+/// # Returns
+/// A tuple `(Option<TxSocket>, Option<RxSocket>)`. The appropriate socket(s) will be
+/// `Some` based on the `direction`.
+pub fn create_shared_socket(
+    umem: &Arc<Umem>,
+    if_index: u32,
+    if_queue: u32,
+    direction: Direction,
+    config: Option<XdpConfig>,
+) -> Result<(Option<TxSocket>, Option<RxSocket>), io::Error> {
+    SocketBuilder::new(if_index)
+        .queue(if_queue)
+        .config(config.unwrap_or_default())
+        .umem(umem)
+        .build(direction)
+}
+
+/// Binds one or two sockets to a UMEM, once the configuration has been validated
+/// by `SocketBuilder`.
 ///
 /// # How it works
 ///
-/// 1.  Creates a raw `AF_XDP` socket.
-/// 2.  Calls `setup_umem` to create a memory-mapped UMEM region.
+/// 1.  Resolves the ring sizes and the split of `frame_count` frames (by default all
+///     free ones) between TX and RX with `ring_sizes`, and takes these frames from
+///     the UMEM frame pool.
+/// 2.  Takes the UMEM registering socket, or creates a raw `AF_XDP` socket to share it.
 /// 3.  Sets the sizes for the Fill, Completion, TX, and RX rings via `setsockopt`.
 /// 4.  Retrieves the memory map offsets for the rings from the kernel.
 /// 5.  Memory-maps the required rings based on the specified `Direction`.
-/// 6.  Binds the socket to the given interface index and queue ID, enabling zero-copy
-///     and need-wakeup flags based on the config.
-/// 7.  Wraps the components in `TxSocket` and/or `RxSocket` and returns them.
+/// 6.  Binds the socket to the given interface index and queue ID. The first socket
+///     of the UMEM enables zero-copy and need-wakeup flags based on the config, the
+///     following ones bind with `XDP_SHARED_UMEM` and inherit these flags.
+/// 7.  Applies the busy-poll socket options from the config.
+/// 8.  Wraps the components in `TxSocket` and/or `RxSocket`, applies the backpressure
+///     policy of the config to the `TxSocket`, and returns them.
+///
+/// On error, the reserved frames go back to the UMEM frame pool, and a claimed
+/// registering socket is replaced so the next socket can take it.
 ///
 /// # Arguments
+/// * `umem` - The UMEM to use for packet frames.
 /// * `if_index` - The index of the network interface to bind to.
 /// * `if_queue` - The queue index of the interface to bind to.
 /// * `direction` - The desired direction(s) for the socket (`Tx`, `Rx`, or `Both`).
-/// * `config` - Optional configuration for zero-copy, huge pages, etc.
+/// * `config` - Optional configuration; `frame_count` is the number of frames to
+///   reserve from the UMEM, the UMEM geometry fields are not used.
 ///
 /// # Returns
 /// A tuple `(Option<TxSocket>, Option<RxSocket>)`. The appropriate socket(s) will be
 /// `Some` based on the `direction`.
-///
-/// # Safety
-/// This function is unsafe because it directly interfaces with low-level Linux APIs.
-/// The caller must ensure the provided parameters are valid.
-pub fn create_socket(
+pub(crate) fn bind_socket(
+    umem: &Arc<Umem>,
     if_index: u32,
     if_queue: u32,
     direction: Direction,
     config: Option<XdpConfig>,
 ) -> Result<(Option<TxSocket>, Option<RxSocket>), io::Error> {
-    let (rx_ring_size, tx_ring_size) = match direction {
-        Direction::Tx => (0, FRAME_COUNT), // all frames for outgoing packets
-        Direction::Rx => (FRAME_COUNT, 0), // all frames for incoming packets
-        Direction::Both => (FRAME_COUNT / 2, FRAME_COUNT / 2), // split frames for both directions
+    let frames = config
+        .and_then(|cfg| cfg.frame_count)
+        .unwrap_or_else(|| umem.free_frames());
+    let sizes = ring_sizes(direction, frames, config.as_ref())?;
+    let frames = umem.reserve(sizes.tx_frames + sizes.rx_frames)?;
+    let shared = match umem.claim(if_index, if_queue) {
+        Ok(owner) => !owner,
+        Err(e) => {
+            umem.release(&frames);
+            return Err(e);
+        }
     };
+    bind_rings(
+        umem, if_index, if_queue, direction, config, &sizes, &frames, shared,
+    )
+    .inspect_err(|_| {
+        if umem.is_private() {
+            // the UMEM is dropped along with the failed attempt
+            return;
+        }
+        // the rings and the socket of the failed attempt are gone, so the kernel
+        //  no longer uses the frames
+        if !shared {
+            if let Err(e) = umem.reset() {
+                log::error!("Failed to release the UMEM socket: {e}");
+                return;
+            }
+        }
+        umem.release(&frames);
+    })
+}
 
-    let (fd, raw_fd) = unsafe {
-        let fd = libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0);
-        if fd < 0 {
-            return Err(io::Error::last_os_error());
+/// Creates the rings of one or two sockets, binds them and wraps them into the
+/// `TxSocket` and/or `RxSocket`, once the frames are reserved and the UMEM claimed.
+///
+/// The new rings and AF_XDP socket are dropped on error.
+#[allow(clippy::too_many_arguments)]
+fn bind_rings(
+    umem: &Arc<Umem>,
+    if_index: u32,
+    if_queue: u32,
+    direction: Direction,
+    config: Option<XdpConfig>,
+    sizes: &RingSizes,
+    frames: &[u64],
+    shared: bool,
+) -> Result<(Option<TxSocket>, Option<RxSocket>), io::Error> {
+    let layout = umem.layout();
+    let (tx_frames, rx_frames) = frames.split_at(sizes.tx_frames);
+    let (fd, raw_fd) = if shared {
+        unsafe {
+            let fd = libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            (Some(OwnedFd::from_raw_fd(fd)), fd)
         }
-        (OwnedFd::from_raw_fd(fd), fd)
+    } else {
+        (None, umem.raw_fd())
     };
-    let umem = setup_umem(raw_fd, config.as_ref())?;
 
-    RingType::Fill.set_size(raw_fd, tx_ring_size)?;
-    RingType::Completion.set_size(raw_fd, tx_ring_size)?;
-    if tx_ring_size > 0 {
-        RingType::Tx.set_size(raw_fd, tx_ring_size)?;
+    RingType::Fill.set_size(raw_fd, sizes.fill)?;
+    RingType::Completion.set_size(raw_fd, sizes.completion)?;
+    if sizes.tx > 0 {
+        RingType::Tx.set_size(raw_fd, sizes.tx)?;
     }
-    if rx_ring_size > 0 {
-        RingType::Rx.set_size(raw_fd, rx_ring_size)?;
+    if sizes.rx > 0 {
+        RingType::Rx.set_size(raw_fd, sizes.rx)?;
     }
 
     let offsets = ring_offsets(raw_fd)?;
@@ -98,11 +225,10 @@ pub fn create_socket(
         (Ring::default(), Ring::default())
     } else {
         (
-            RingType::Completion.mmap(raw_fd, &offsets, tx_ring_size)?,
+            RingType::Completion.mmap(raw_fd, &offsets, sizes.completion)?,
             {
-                let mut tx_ring: Ring<XdpDesc> =
-                    RingType::Tx.mmap(raw_fd, &offsets, tx_ring_size)?;
-                tx_ring.fill(0);
+                let mut tx_ring: Ring<XdpDesc> = RingType::Tx.mmap(raw_fd, &offsets, sizes.tx)?;
+                tx_ring.fill(&layout, tx_frames);
                 tx_ring
             },
         )
@@ -112,14 +238,18 @@ pub fn create_socket(
     let (rx_ring, f_ring) = if direction == Direction::Tx {
         (Ring::default(), Ring::default())
     } else {
-        (RingType::Rx.mmap(raw_fd, &offsets, rx_ring_size)?, {
-            let mut f_ring: Ring<u64> = RingType::Fill.mmap(raw_fd, &offsets, rx_ring_size)?;
-            f_ring.fill(tx_ring_size as u32);
-            f_ring.update_producer(f_ring.len as u32);
+        (RingType::Rx.mmap(raw_fd, &offsets, sizes.rx)?, {
+            let mut f_ring: Ring<u64> = RingType::Fill.mmap(raw_fd, &offsets, sizes.fill)?;
+            f_ring.fill(rx_frames);
+            f_ring.update_producer(sizes.rx_frames as u32);
             f_ring
         })
     };
 
+    let require_zero_copy = config
+        .and_then(|cfg| cfg.require_zero_copy)
+        .unwrap_or(false);
+
     let zero_copy = match config.and_then(|cfg| cfg.zero_copy) {
         Some(true) => libc::XDP_ZEROCOPY,
         Some(false) => libc::XDP_COPY,
@@ -132,12 +262,22 @@ pub fn create_socket(
         0
     };
 
+    let multi_buffer = config.and_then(|cfg| cfg.multi_buffer).unwrap_or(false);
+    let use_sg = if multi_buffer { libc::XDP_USE_SG } else { 0 };
+
+    // shared sockets must not pass bind flags, they are inherited from the UMEM socket
+    let (flags, shared_umem_fd) = if shared {
+        (libc::XDP_SHARED_UMEM, umem.raw_fd() as u32)
+    } else {
+        (need_wakeup | zero_copy | use_sg, 0)
+    };
+
     let sxdp = libc::sockaddr_xdp {
         sxdp_family: libc::AF_XDP as libc::sa_family_t,
-        sxdp_flags: need_wakeup | zero_copy,
+        sxdp_flags: flags,
         sxdp_ifindex: if_index,
         sxdp_queue_id: if_queue,
-        sxdp_shared_umem_fd: 0,
+        sxdp_shared_umem_fd: shared_umem_fd,
     };
 
     if unsafe {
@@ -152,21 +292,43 @@ pub fn create_socket(
             io::Error::last_os_error()
         )));
     }
+    if require_zero_copy && !xdp_options(raw_fd)?.is_zero_copy() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "interface {if_index} queue {if_queue} is bound in copy mode, \
+                 but zero-copy mode is required"
+            ),
+        ));
+    }
+    if !shared {
+        umem.set_bound(if_index, if_queue, multi_buffer);
+    }
+    let busy_poll = set_busy_poll(raw_fd, config.as_ref())?;
 
     // its just owned shared memory and socket descriptor
     // that we can share between Tx and Rx sockets
     // to release it when both are destroyed
     #[allow(clippy::arc_with_non_send_sync)]
-    let inner = Arc::new(Inner::new(umem, fd));
+    let inner = Arc::new(Inner::new(umem.clone(), fd, busy_poll));
 
     let tx_socket = if direction != Direction::Rx {
-        Some(TxSocket::new(Some(inner.clone()), tx_ring, c_ring))
+        let mut tx_socket = TxSocket::new(Some(inner.clone()), tx_ring, c_ring, tx_frames);
+        if let Some(backpressure) = config.as_ref().and_then(|cfg| cfg.backpressure) {
+            tx_socket.set_backpressure(backpressure);
+        }
+        Some(tx_socket)
     } else {
         None
     };
 
     let rx_socket = if direction != Direction::Tx {
-        Some(RxSocket::new(Some(inner.clone()), rx_ring, f_ring))
+        Some(RxSocket::new(
+            Some(inner.clone()),
+            rx_ring,
+            f_ring,
+            rx_frames,
+        ))
     } else {
         None
     };
@@ -238,6 +400,102 @@ pub fn create_bi_socket(
     ))
 }
 
+/// Creates one or two sockets bound to the interface named `if_name`.
+///
+/// This is a thin wrapper around `SocketBuilder::with_name`, see `create_socket`.
+///
+/// # Arguments
+/// * `if_name` - The name of the network interface to bind to, e.g. `eth0`.
+/// * `if_queue` - The queue index of the interface to bind to.
+/// * `direction` - The desired direction(s) for the socket (`Tx`, `Rx`, or `Both`).
+/// * `config` - Optional configuration for zero-copy, huge pages, etc.
+///
+/// # Errors
+/// Returns an `io::Error` of kind `NotFound` if there is no such interface, or any
+/// error of `create_socket`.
+pub fn create_socket_by_name(
+    if_name: &str,
+    if_queue: u32,
+    direction: Direction,
+    config: Option<XdpConfig>,
+) -> Result<(Option<TxSocket>, Option<RxSocket>), io::Error> {
+    SocketBuilder::with_name(if_name)
+        .queue(if_queue)
+        .config(config.unwrap_or_default())
+        .build(direction)
+}
+
+/// Creates a `TxSocket` bound to the interface named `if_name`.
+///
+/// See `create_tx_socket` and `create_socket_by_name`.
+pub fn create_tx_socket_by_name(
+    if_name: &str,
+    if_queue: u32,
+    config: Option<XdpConfig>,
+) -> Result<TxSocket, io::Error> {
+    let (tx_socket, _) = create_socket_by_name(if_name, if_queue, Direction::Tx, config)?;
+    tx_socket.ok_or_else(|| io::Error::other("Failed to create Tx socket"))
+}
+
+/// Creates an `RxSocket` bound to the interface named `if_name`.
+///
+/// See `create_rx_socket` and `create_socket_by_name`.
+pub fn create_rx_socket_by_name(
+    if_name: &str,
+    if_queue: u32,
+    config: Option<XdpConfig>,
+) -> Result<RxSocket, io::Error> {
+    let (_, rx_socket) = create_socket_by_name(if_name, if_queue, Direction::Rx, config)?;
+    rx_socket.ok_or_else(|| io::Error::other("Failed to create Rx socket"))
+}
+
+/// Creates a pair of sockets (`TxSocket`, `RxSocket`) bound to the interface named
+/// `if_name`.
+///
+/// See `create_bi_socket` and `create_socket_by_name`.
+pub fn create_bi_socket_by_name(
+    if_name: &str,
+    if_queue: u32,
+    config: Option<XdpConfig>,
+) -> Result<(TxSocket, RxSocket), io::Error> {
+    let (tx_socket, rx_socket) = create_socket_by_name(if_name, if_queue, Direction::Both, config)?;
+    Ok((
+        tx_socket.ok_or_else(|| io::Error::other("Failed to create Tx socket"))?,
+        rx_socket.ok_or_else(|| io::Error::other("Failed to create Rx socket"))?,
+    ))
+}
+
+/// Resolves the name of a network interface to its index with `if_nametoindex`.
+///
+/// # Arguments
+/// * `if_name` - The name of the network interface, e.g. `eth0`.
+///
+/// # Returns
+/// A `Result` containing the interface index.
+///
+/// # Errors
+/// Returns an `io::Error` of kind `NotFound` if there is no interface with this name,
+/// or `InvalidInput` if the name contains a NUL byte.
+pub fn if_index_by_name(if_name: &str) -> io::Result<u32> {
+    let name = CString::new(if_name).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid interface name {if_name:?}"),
+        )
+    })?;
+    let if_index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if if_index == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No such interface {if_name:?}: {}",
+                io::Error::last_os_error()
+            ),
+        ));
+    }
+    Ok(if_index)
+}
+
 /// Retrieves the memory map offsets for the AF_XDP rings from the kernel.
 ///
 /// This function uses `getsockopt` with `XDP_MMAP_OFFSETS` to query the kernel for
@@ -264,33 +522,240 @@ pub fn ring_offsets(raw_fd: libc::c_int) -> io::Result<libc::xdp_mmap_offsets> {
     Ok(offsets)
 }
 
+/// Resolves the ring sizes and the split of UMEM frames between TX and RX.
+///
+/// # How it works
+///
+/// TX only and RX only sockets own all UMEM frames, a bidirectional socket gives
+/// `rx_frames` (half by default) to RX and the rest to TX. Free TX frames are parked
+/// in the TX ring and all RX frames are posted to the Fill ring at once, so these
+/// rings must be large enough to hold them. Ring sizes that are not configured are
+/// derived from the frame split.
+///
+/// # Arguments
+/// * `direction` - The desired direction(s) for the socket.
+/// * `frame_count` - The number of UMEM frames owned by the socket(s).
+/// * `config` - Optional configuration carrying the ring sizes and the frame split.
+///
+/// # Errors
+/// Returns an `io::Error` of kind `InvalidInput` if a ring size is not a power of two,
+/// the frame split is out of range, or a ring cannot hold the frames given to it.
+pub fn ring_sizes(
+    direction: Direction,
+    frame_count: usize,
+    config: Option<&XdpConfig>,
+) -> io::Result<RingSizes> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    if frame_count == 0 {
+        return Err(invalid("No UMEM frames for the socket".to_string()));
+    }
+
+    let (tx_frames, rx_frames) = match direction {
+        Direction::Tx => (frame_count, 0), // all frames for outgoing packets
+        Direction::Rx => (0, frame_count), // all frames for incoming packets
+        Direction::Both => {
+            // split frames for both directions
+            let rx_frames = config
+                .and_then(|cfg| cfg.rx_frames)
+                .unwrap_or(frame_count / 2);
+            if rx_frames == 0 || rx_frames >= frame_count {
+                return Err(invalid(format!(
+                    "Invalid RX frames {rx_frames}, expected 1..{frame_count}"
+                )));
+            }
+            (frame_count - rx_frames, rx_frames)
+        }
+    };
+
+    let ring_size = |name: &str, value: Option<usize>, default: usize| match value {
+        Some(size) if !size.is_power_of_two() => Err(invalid(format!(
+            "Invalid {name} ring size {size}, expected a power of two"
+        ))),
+        Some(size) => Ok(size),
+        None => Ok(default),
+    };
+
+    let (tx, completion) = if tx_frames > 0 {
+        let tx = ring_size(
+            "TX",
+            config.and_then(|cfg| cfg.tx_ring_size),
+            tx_frames.next_power_of_two(),
+        )?;
+        if tx < tx_frames {
+            return Err(invalid(format!(
+                "TX ring size {tx} cannot hold {tx_frames} TX frames"
+            )));
+        }
+        let completion = ring_size(
+            "Completion",
+            config.and_then(|cfg| cfg.completion_ring_size),
+            tx,
+        )?;
+        (tx, completion)
+    } else {
+        (0, 0)
+    };
+
+    let (rx, fill) = if rx_frames > 0 {
+        let rx = ring_size(
+            "RX",
+            config.and_then(|cfg| cfg.rx_ring_size),
+            rx_frames.next_power_of_two(),
+        )?;
+        let fill = ring_size("Fill", config.and_then(|cfg| cfg.fill_ring_size), rx)?;
+        if fill < rx_frames {
+            return Err(invalid(format!(
+                "Fill ring size {fill} cannot hold {rx_frames} RX frames"
+            )));
+        }
+        (rx, fill)
+    } else {
+        (0, 0)
+    };
+
+    Ok(RingSizes {
+        tx,
+        rx,
+        fill,
+        completion,
+        tx_frames,
+        rx_frames,
+    })
+}
+
+/// The headroom the kernel reserves in front of every received packet.
+const XDP_PACKET_HEADROOM: usize = 256;
+
+/// Builds the UMEM frame layout from the configuration and validates it.
+///
+/// Missing values are replaced with `FRAME_SIZE`, `FRAME_COUNT`, zero headroom, no
+/// TX metadata and aligned chunks.
+///
+/// # Arguments
+/// * `config` - Optional configuration carrying the frame size, frame count, headroom,
+///   TX metadata length and chunk mode.
+///
+/// # Errors
+/// Returns an `io::Error` of kind `InvalidInput` if the frame size is neither 2048 nor
+/// 4096 (or out of that range in unaligned mode), the frame count is zero, the
+/// headroom leaves no room for packet data, or the TX metadata length is not accepted
+/// by the kernel.
+pub fn frame_layout(config: Option<&XdpConfig>) -> io::Result<FrameLayout> {
+    let frame_size = config.and_then(|cfg| cfg.frame_size).unwrap_or(FRAME_SIZE);
+    let frame_count = config
+        .and_then(|cfg| cfg.frame_count)
+        .unwrap_or(FRAME_COUNT);
+    let headroom = config.and_then(|cfg| cfg.headroom).unwrap_or(0);
+    let tx_metadata_len = config.and_then(|cfg| cfg.tx_metadata_len).unwrap_or(0);
+    let unaligned = config.and_then(|cfg| cfg.unaligned_chunks).unwrap_or(false);
+
+    if unaligned && !(2048..=4096).contains(&frame_size) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid frame size {frame_size}, expected 2048 to 4096"),
+        ));
+    }
+    if !unaligned && frame_size != 2048 && frame_size != 4096 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid frame size {frame_size}, expected 2048 or 4096"),
+        ));
+    }
+    if frame_count == 0 || frame_count > u32::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid frame count {frame_count}"),
+        ));
+    }
+    // the kernel reserves XDP_PACKET_HEADROOM (256 bytes) in front of received packets
+    if headroom + XDP_PACKET_HEADROOM >= frame_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid headroom {headroom} for frame size {frame_size}"),
+        ));
+    }
+    // the kernel limits the metadata area to 8-byte multiples below 256 bytes, and
+    //  it must hold a TxMetadata aligned like the frames
+    if tx_metadata_len != 0
+        && (tx_metadata_len < size_of::<TxMetadata>()
+            || tx_metadata_len >= 256
+            || tx_metadata_len % 8 != 0
+            || headroom % 8 != 0
+            || frame_size % 8 != 0)
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Invalid TX metadata length {tx_metadata_len} with headroom {headroom}, \
+                 expected a multiple of 8 from {} to 248 and a headroom and frame size \
+                 multiple of 8",
+                size_of::<TxMetadata>()
+            ),
+        ));
+    }
+
+    Ok(FrameLayout {
+        frame_size: frame_size as u32,
+        frame_count: frame_count as u32,
+        headroom: headroom as u32,
+        tx_metadata_len: tx_metadata_len as u32,
+        unaligned,
+    })
+}
+
 /// Allocates and registers the UMEM (Userspace Memory) region with the kernel.
 ///
 /// # How it works
 ///
-/// 1.  It calls `OwnedMmap::mmap` to create a memory-mapped region, optionally
-///     backed by huge pages.
-/// 2.  It populates an `xdp_umem_reg` struct with the address and size of the UMEM.
+/// 1.  It calls `OwnedMmap::mmap` to create a memory-mapped region of
+///     `frame_size * frame_count` bytes, optionally backed by huge pages.
+/// 2.  It populates an `xdp_umem_reg` struct with the address and size of the UMEM,
+///     the frame size, the per-frame headroom, the TX metadata length and the
+///     chunk mode flags.
 /// 3.  It calls `setsockopt` with `XDP_UMEM_REG` to register the UMEM with the
 ///     kernel, making it available for zero-copy operations.
 ///
 /// # Arguments
 /// * `raw_fd` - The raw file descriptor of the AF_XDP socket.
+/// * `layout` - The geometry of the UMEM frames.
 /// * `config` - Optional configuration, used to determine if huge pages should be used.
-pub fn setup_umem(raw_fd: libc::c_int, config: Option<&XdpConfig>) -> io::Result<OwnedMmap> {
-    let umem = OwnedMmap::mmap(
-        FRAME_COUNT * FRAME_SIZE,
-        config.and_then(|cfg| cfg.huge_page),
-    )
-    .map_err(|e| io::Error::other(format!("Failed to allocate UMEM: {}", e)))?;
-
-    let reg = unsafe {
-        libc::xdp_umem_reg {
-            addr: umem.as_void_ptr() as u64,
-            len: umem.len() as u64,
-            chunk_size: FRAME_SIZE as u32,
-            ..std::mem::zeroed()
-        }
+pub fn setup_umem(
+    raw_fd: libc::c_int,
+    layout: &FrameLayout,
+    config: Option<&XdpConfig>,
+) -> io::Result<OwnedMmap> {
+    let umem = OwnedMmap::mmap(layout.umem_size(), config.and_then(|cfg| cfg.huge_page))
+        .map_err(|e| io::Error::other(format!("Failed to allocate UMEM: {}", e)))?;
+    register_umem(raw_fd, &umem, layout)?;
+    Ok(umem)
+}
+
+/// Registers an allocated UMEM region with the kernel on an AF_XDP socket.
+///
+/// # Arguments
+/// * `raw_fd` - The raw file descriptor of the AF_XDP socket.
+/// * `umem` - The memory-mapped UMEM region.
+/// * `layout` - The geometry of the UMEM frames.
+pub(crate) fn register_umem(
+    raw_fd: libc::c_int,
+    umem: &OwnedMmap,
+    layout: &FrameLayout,
+) -> io::Result<()> {
+    let mut flags = 0;
+    if layout.tx_metadata_len > 0 {
+        flags |= libc::XDP_UMEM_TX_METADATA_LEN;
+    }
+    if layout.unaligned {
+        flags |= libc::XDP_UMEM_UNALIGNED_CHUNK_FLAG;
+    }
+
+    let reg = libc::xdp_umem_reg {
+        addr: umem.as_void_ptr() as u64,
+        len: umem.len() as u64,
+        chunk_size: layout.frame_size,
+        headroom: layout.headroom,
+        flags,
+        tx_metadata_len: layout.tx_metadata_len,
     };
 
     unsafe {
@@ -309,7 +774,7 @@ pub fn setup_umem(raw_fd: libc::c_int, config: Option<&XdpConfig>) -> io::Result
         }
     }
 
-    Ok(umem)
+    Ok(())
 }
 
 /// Specifies the direction of an AF_XDP socket.
@@ -332,7 +797,17 @@ pub struct XdpConfig {
     /// - `Some(true)`: Enables `XDP_ZEROCOPY`.
     /// - `Some(false)`: Enables `XDP_COPY`.
     /// - `None`: The kernel's default behavior is used (typically copy mode).
+    ///
+    /// The mode actually chosen can be checked with `Socket::is_zero_copy`.
     pub zero_copy: Option<bool>,
+    /// Requires the socket to be bound in zero-copy mode.
+    ///
+    /// Sockets sharing a UMEM inherit the mode of the first socket bound to it.
+    ///
+    /// - `Some(true)`: Socket creation fails with an `Unsupported` error if the
+    ///   kernel bound the socket in copy mode.
+    /// - `Some(false)` or `None`: Any mode is accepted.
+    pub require_zero_copy: Option<bool>,
     /// Enables or disables huge pages for the UMEM.
     ///
     /// - `Some(true)`: Attempts to use huge pages.
@@ -345,4 +820,240 @@ pub struct XdpConfig {
     /// - `Some(false)`: The flag is not set. The kernel polls without needing a wakeup call.
     /// - `None`: Defaults to `true`.
     pub need_wakeup: Option<bool>,
+    /// Sets the size of a single UMEM frame in bytes.
+    ///
+    /// - `Some(2048)` or `Some(4096)`: Uses the given frame size. In unaligned mode
+    ///   any size from 2048 to 4096 is accepted.
+    /// - `None`: Defaults to `FRAME_SIZE` (2048).
+    pub frame_size: Option<usize>,
+    /// Sets the number of frames in the UMEM.
+    ///
+    /// With `create_shared_socket` this is the number of frames the socket reserves
+    /// from the shared UMEM instead, and it is required: sockets sharing a UMEM
+    /// split its frames as chosen by the application.
+    ///
+    /// - `Some(n)`: Allocates `n` frames.
+    /// - `None`: Defaults to `FRAME_COUNT` (4096); rejected with a shared UMEM.
+    pub frame_count: Option<usize>,
+    /// Sets the number of bytes reserved at the start of every frame.
+    ///
+    /// The kernel places received packets after this headroom, and transmitted
+    /// packets are written after it as well.
+    ///
+    /// - `Some(n)`: Reserves `n` bytes.
+    /// - `None`: Defaults to `0`.
+    pub headroom: Option<usize>,
+    /// Sets the size of the TX metadata area placed in front of transmitted packets.
+    ///
+    /// The area holds a `TxMetadata`, written with `peek_metadata`, to request
+    /// checksum offload, TX timestamps or a launch time. It is placed after the
+    /// headroom, so TX frames hold that many bytes less packet data. Requires
+    /// Linux 6.8 or newer.
+    ///
+    /// - `Some(n)`: Reserves `n` bytes, a multiple of 8 of at least
+    ///   `size_of::<TxMetadata>()` and below 256; the headroom must be a multiple
+    ///   of 8 as well.
+    /// - `None`: Defaults to `0`, no TX metadata.
+    pub tx_metadata_len: Option<usize>,
+    /// Registers the UMEM with `XDP_UMEM_UNALIGNED_CHUNK_FLAG`.
+    ///
+    /// Frames no longer need a power of two size, so variably sized packets can be
+    /// packed densely, and packets may start at any offset of their frame, e.g. to
+    /// prepend encapsulation headers in place with `adjust_head`. Received
+    /// descriptors then carry the packet offset in their upper 16 bits, which the
+    /// socket resolves transparently. Frames that are not a power of two in size may
+    /// cross page boundaries, so huge pages are recommended with zero-copy.
+    ///
+    /// - `Some(true)`: Uses unaligned chunks.
+    /// - `Some(false)` or `None`: Uses aligned chunks.
+    pub unaligned_chunks: Option<bool>,
+    /// Sets the number of descriptors in the TX ring, must be a power of two.
+    ///
+    /// - `Some(n)`: Uses `n` descriptors, at least the number of TX frames.
+    /// - `None`: The number of TX frames rounded up to a power of two.
+    pub tx_ring_size: Option<usize>,
+    /// Sets the number of descriptors in the RX ring, must be a power of two.
+    ///
+    /// - `Some(n)`: Uses `n` descriptors.
+    /// - `None`: The number of RX frames rounded up to a power of two.
+    pub rx_ring_size: Option<usize>,
+    /// Sets the number of entries in the Fill ring, must be a power of two.
+    ///
+    /// - `Some(n)`: Uses `n` entries, at least the number of RX frames.
+    /// - `None`: The same as the RX ring size.
+    pub fill_ring_size: Option<usize>,
+    /// Sets the number of entries in the Completion ring, must be a power of two.
+    ///
+    /// - `Some(n)`: Uses `n` entries.
+    /// - `None`: The same as the TX ring size.
+    pub completion_ring_size: Option<usize>,
+    /// Sets how many UMEM frames are given to the RX side of a bidirectional socket.
+    ///
+    /// Only used with `Direction::Both`; TX and RX only sockets own all frames.
+    ///
+    /// - `Some(n)`: `n` frames go to RX, the remaining frames go to TX.
+    /// - `None`: The frames are split in half.
+    pub rx_frames: Option<usize>,
+    /// Sets the `XDP_USE_SG` flag to enable multi-buffer packets.
+    ///
+    /// Packets larger than a frame, like jumbo frames, then span a chain of
+    /// descriptors linked with `XDP_PKT_CONTD`. Sockets sharing a UMEM inherit
+    /// the setting of the first socket bound to it.
+    ///
+    /// - `Some(true)`: The flag is set.
+    /// - `Some(false)` or `None`: Every packet fits in a single frame.
+    pub multi_buffer: Option<bool>,
+    /// Sets `SO_BUSY_POLL`, the time in microseconds a syscall busy polls the device
+    /// queue for new packets.
+    ///
+    /// A busy-polling socket makes a syscall on every `kick`, and whenever `seek`
+    /// finds no new descriptors, to drive the driver from the application thread.
+    ///
+    /// - `Some(n)`: Busy polls for up to `n` microseconds, `0` disables it.
+    /// - `None`: The `net.core.busy_read` sysctl default is used.
+    pub busy_poll: Option<u32>,
+    /// Sets `SO_PREFER_BUSY_POLL`, which defers the device interrupts while the
+    /// application keeps busy polling. Requires `busy_poll`.
+    ///
+    /// - `Some(true)`: Busy polling is preferred over interrupts.
+    /// - `Some(false)` or `None`: Interrupts are handled as usual.
+    pub prefer_busy_poll: Option<bool>,
+    /// Sets `SO_BUSY_POLL_BUDGET`, the number of packets processed by a single busy
+    /// poll. Requires `busy_poll`.
+    ///
+    /// - `Some(n)`: Processes up to `n` packets per poll.
+    /// - `None`: The kernel default (8) is used.
+    pub busy_poll_budget: Option<u16>,
+    /// Sets what the `send` methods of a TX socket do when no frame is free, see
+    /// `Backpressure`. It can be changed later with `Socket::set_backpressure`.
+    ///
+    /// - `Some(policy)`: Uses the given policy.
+    /// - `None`: Defaults to `Backpressure::Fail`, returning `RingError::RingFull`.
+    pub backpressure: Option<Backpressure>,
+}
+
+/// Ring sizes and the split of UMEM frames resolved from an `XdpConfig`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct RingSizes {
+    /// The number of descriptors in the TX ring, zero if there is no TX side.
+    pub tx: usize,
+    /// The number of descriptors in the RX ring, zero if there is no RX side.
+    pub rx: usize,
+    /// The number of entries in the Fill ring.
+    pub fill: usize,
+    /// The number of entries in the Completion ring.
+    pub completion: usize,
+    /// The number of UMEM frames owned by the TX side.
+    pub tx_frames: usize,
+    /// The number of UMEM frames owned by the RX side.
+    pub rx_frames: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_sizes_default_to_frame_split() {
+        let sizes = ring_sizes(Direction::Both, 4096, None).unwrap();
+        assert_eq!(sizes.tx_frames, 2048);
+        assert_eq!(sizes.rx_frames, 2048);
+        assert_eq!((sizes.tx, sizes.completion), (2048, 2048));
+        assert_eq!((sizes.rx, sizes.fill), (2048, 2048));
+
+        let sizes = ring_sizes(Direction::Tx, 3000, None).unwrap();
+        assert_eq!((sizes.tx_frames, sizes.rx_frames), (3000, 0));
+        assert_eq!(
+            (sizes.tx, sizes.completion, sizes.rx, sizes.fill),
+            (4096, 4096, 0, 0)
+        );
+    }
+
+    #[test]
+    fn ring_sizes_honor_config() {
+        let config = XdpConfig {
+            rx_frames: Some(1024),
+            rx_ring_size: Some(512),
+            fill_ring_size: Some(1024),
+            completion_ring_size: Some(256),
+            ..Default::default()
+        };
+        let sizes = ring_sizes(Direction::Both, 4096, Some(&config)).unwrap();
+        assert_eq!((sizes.tx_frames, sizes.rx_frames), (3072, 1024));
+        assert_eq!((sizes.tx, sizes.completion), (4096, 256));
+        assert_eq!((sizes.rx, sizes.fill), (512, 1024));
+    }
+
+    #[test]
+    fn ring_sizes_reject_invalid_config() {
+        let invalid = |config: XdpConfig, direction| {
+            ring_sizes(direction, 4096, Some(&config))
+                .unwrap_err()
+                .kind()
+                == io::ErrorKind::InvalidInput
+        };
+        assert!(invalid(
+            XdpConfig {
+                tx_ring_size: Some(3000),
+                ..Default::default()
+            },
+            Direction::Tx
+        ));
+        assert!(invalid(
+            XdpConfig {
+                tx_ring_size: Some(2048),
+                ..Default::default()
+            },
+            Direction::Tx
+        ));
+        assert!(invalid(
+            XdpConfig {
+                fill_ring_size: Some(1024),
+                ..Default::default()
+            },
+            Direction::Rx
+        ));
+        assert!(invalid(
+            XdpConfig {
+                rx_frames: Some(4096),
+                ..Default::default()
+            },
+            Direction::Both
+        ));
+        assert!(ring_sizes(Direction::Rx, 0, None).is_err());
+    }
+
+    #[test]
+    fn frame_layout_places_tx_metadata_before_data() {
+        let config = XdpConfig {
+            frame_size: Some(4096),
+            headroom: Some(64),
+            tx_metadata_len: Some(size_of::<TxMetadata>()),
+            ..Default::default()
+        };
+        let layout = frame_layout(Some(&config)).unwrap();
+        assert_eq!(layout.tx_metadata_len as usize, size_of::<TxMetadata>());
+        assert_eq!(layout.data_offset(), 64 + size_of::<TxMetadata>() as u64);
+        assert_eq!(layout.capacity(), 4096 - 64 - size_of::<TxMetadata>());
+        assert_eq!(frame_layout(None).unwrap().data_offset(), 0);
+    }
+
+    #[test]
+    fn frame_layout_rejects_invalid_tx_metadata() {
+        let invalid = |headroom, tx_metadata_len| {
+            let config = XdpConfig {
+                headroom: Some(headroom),
+                tx_metadata_len: Some(tx_metadata_len),
+                ..Default::default()
+            };
+            frame_layout(Some(&config)).is_err_and(|err| err.kind() == io::ErrorKind::InvalidInput)
+        };
+        // too small for a TxMetadata, not a multiple of 8, too large
+        assert!(invalid(0, 8));
+        assert!(invalid(0, 28));
+        assert!(invalid(0, 256));
+        // the metadata area must stay 8-byte aligned
+        assert!(invalid(4, 24));
+        assert!(!invalid(8, 248));
+    }
 }
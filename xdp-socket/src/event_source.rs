@@ -0,0 +1,65 @@
+//!
+//! # Mio Event Source
+//!
+//! This file implements `mio::event::Source` for `Socket`, so XDP sockets can be
+//! registered with a `mio::Poll` alongside TCP listeners, timers and other sources.
+//! It is available with the `mio` feature.
+//!
+//! ## How it works
+//!
+//! Registration is delegated to `SourceFd` over the socket's raw file descriptor.
+//! The readiness follows the same semantics as `PollWaitExt::poll_wait`:
+//! - Transmit sockets (`_TX`) become writable when the kernel has consumed TX
+//!   descriptors, register them with `Interest::WRITABLE`.
+//! - Receive sockets (`_RX`) become readable when packets arrive in the RX ring,
+//!   register them with `Interest::READABLE`.
+//!
+//! Mio events are edge-triggered and the kernel only processes the rings of a socket
+//! bound with need-wakeup after a wakeup call, so call `kick` before waiting and
+//! drain the socket with `seek` until it fails after every event.
+//!
+//! The two halves of a bidirectional socket share one file descriptor, and epoll
+//! accepts a file descriptor only once: registering the second half fails with
+//! `AlreadyExists`. Register only one of the halves with
+//! `Interest::READABLE | Interest::WRITABLE` and dispatch each event to the half
+//! matching its readiness. Deregister through the same half before dropping either.
+//!
+//! ## Main components
+//!
+//! - `impl Source for Socket<t>`: Registration of XDP sockets with a mio `Registry`.
+//!
+
+#![allow(non_upper_case_globals)]
+
+use crate::socket::{_Direction, Socket};
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token, event};
+use std::io;
+
+/// Registers the socket's file descriptor with a mio `Registry`.
+///
+/// For a bidirectional socket, only one half may be registered, with the union of the
+/// interests of both halves.
+impl<const t: _Direction> event::Source for Socket<t> {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.raw_fd).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.raw_fd).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.raw_fd).deregister(registry)
+    }
+}
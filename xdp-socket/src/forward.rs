@@ -0,0 +1,102 @@
+//! # Zero-Copy Packet Forwarding
+//!
+//! ## Purpose
+//!
+//! This file implements forwarding of received packets from an `RxSocket` to a
+//! `TxSocket` without copying the payload. Both sockets must use the same UMEM: the
+//! two halves of `create_bi_socket`, or sockets bound to a shared `Umem` on different
+//! queues or interfaces.
+//!
+//! ## How it works
+//!
+//! Forwarding swaps frames between the two sides instead of moving them. The RX
+//! descriptor is written into the next available TX descriptor, and the free frame
+//! that TX descriptor was pointing to takes the place of the received frame in the
+//! RX ring. Committing the RX side then hands this free frame to the Fill ring, while
+//! committing the TX side submits the received frame for transmission. Once the
+//! kernel has sent it, the frame comes back on the Completion ring and is recycled
+//! into the TX pool by `seek`. Both pools keep their size, so no frame is ever lost
+//! or copied.
+//!
+//! ## Main components
+//!
+//! - `impl Socket<_RX>`: Provides `forward` and `forward_n`.
+
+use crate::ring::XdpDesc;
+use crate::socket::{_RX, _TX, Commit_, RingError, Socket};
+
+impl Socket<_RX> {
+    /// Forwards the first available received packet to a transmit socket.
+    ///
+    /// See `forward_n` for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transmit socket, using the same UMEM as this socket.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or a `RingError` on failure.
+    #[inline]
+    pub fn forward(&mut self, tx: &mut Socket<_TX>) -> Result<(), RingError> {
+        self.forward_n(tx, 1)
+    }
+
+    /// Forwards the first `count` available received packets to a transmit socket
+    /// without copying them.
+    ///
+    /// The received frames are placed into the first `count` available descriptors
+    /// of `tx` and committed to the TX ring, while the free TX frames are committed
+    /// to the Fill ring of this socket in their place. Packets can be modified in
+    /// place with `peek_mut` before forwarding. The caller must make `count` TX
+    /// descriptors available with `seek_n` beforehand, and call `kick` on `tx`
+    /// afterwards. Anything written to those TX descriptors with `peek` is discarded.
+    ///
+    /// Fragments of a multi-buffer packet are forwarded as they are, so `count` must
+    /// cover whole packets (see `seek_packet`) and `tx` must be a multi-buffer socket
+    /// as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transmit socket, using the same UMEM as this socket.
+    /// * `count` - The number of packets to forward.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success, or a `RingError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::UmemMismatch` if the sockets do not share the same UMEM,
+    /// and `RingError::NotAvailable` if fewer than `count` packets are received or
+    /// fewer than `count` TX descriptors are available.
+    pub fn forward_n(&mut self, tx: &mut Socket<_TX>, count: usize) -> Result<(), RingError> {
+        if !self.shares_umem_with(tx) {
+            return Err(RingError::UmemMismatch);
+        }
+        #[cfg(not(feature = "no_safety_checks"))]
+        if self.available < count as u32 || tx.available < count as u32 {
+            return Err(RingError::NotAvailable);
+        }
+        for i in 0..count as u32 {
+            let rx_head = self.consumer.wrapping_add(i) & self.x_ring.mod_mask;
+            let tx_head = tx.producer.wrapping_add(i) & tx.x_ring.mod_mask;
+            let rx_desc = self.x_ring.desc_at(rx_head);
+            let free_addr = tx.x_ring.desc_at(tx_head).addr;
+            // options are kept so multi-buffer chains are forwarded as a whole
+            *tx.x_ring.mut_desc_at(tx_head) =
+                XdpDesc::new(rx_desc.addr, rx_desc.len, rx_desc.options);
+            // the free TX frame goes to the Fill ring when the RX side is committed, a
+            //  cookie set on it belongs to the packet sent in its place
+            self.x_ring.mut_desc_at(rx_head).addr = free_addr;
+            tx.cookies
+                .replace_frame(&tx.layout, free_addr, rx_desc.addr);
+            self.owned.remove(rx_desc.addr);
+            self.owned.insert(free_addr);
+            tx.owned.remove(free_addr);
+            tx.owned.insert(rx_desc.addr);
+        }
+        Commit_::<_RX>::commit_(self, count)?;
+        Commit_::<_TX>::commit_(tx, count)
+    }
+}
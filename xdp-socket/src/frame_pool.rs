@@ -0,0 +1,304 @@
+//! # UMEM Frame Pool
+//!
+//! ## Purpose
+//!
+//! This file implements `FramePool`, the free-frame allocator of a `Umem`. It hands out
+//! and takes back frame addresses independently of any ring position, so the frames of
+//! a UMEM can be held by the application for as long as it needs them, and the TX, RX
+//! and Fill rings of every socket sharing the UMEM draw from the same pool.
+//!
+//! ## How it works
+//!
+//! The pool is a stack of free frame base addresses behind a mutex, holding every frame
+//! of the UMEM when it is created. Sockets take their initial frames from it when they
+//! are bound. An `RxSocket` keeps a fixed number of frames in circulation: frames taken
+//! out with `take_frame` leave the socket, and the socket tops its Fill ring up from the
+//! pool on its next `seek` or `commit`. Dropped `RxFrame` handles give their frame back
+//! to the pool, as does `free` for frames allocated by the application, so freed frames
+//! reach the Fill ring through these refills.
+//!
+//! The application writes the packet data of a frame it allocated with
+//! `Umem::frame_mut` and transmits it with `send_frame`. The frame takes the place of
+//! the next free frame of the `TxSocket`, which goes back to the pool in exchange, and
+//! stays with the socket once the kernel has completed it.
+//!
+//! Every socket records the frames it owns in a `FrameSet`, updated when frames change
+//! hands with `take_frame`, `forward` or a Fill ring refill. When a socket is closed,
+//! the kernel no longer uses its frames and they go back to the pool.
+//!
+//! ## Main components
+//!
+//! - `FramePool`: The free frames of a UMEM, with `alloc` and `free` methods.
+//! - `FrameSet`: The frames owned by a socket.
+//! - `impl Socket<_TX>`: Provides `send_frame`.
+
+use crate::ring::FrameLayout;
+use crate::socket::{_TX, Commit_ as _, RingError, Socket};
+use std::sync::Mutex;
+
+/// The free frames of a UMEM.
+///
+/// Addresses handed out are frame base addresses, the UMEM offset of the first
+/// byte of a frame; packet data starts `FrameLayout::data_offset` bytes later.
+pub struct FramePool {
+    /// The base addresses of the free frames.
+    free: Mutex<Vec<u64>>,
+    /// The geometry of the UMEM frames.
+    layout: FrameLayout,
+}
+
+impl FramePool {
+    /// Creates a pool holding every frame of the given layout.
+    pub(crate) fn new(layout: FrameLayout) -> Self {
+        // frames are handed out from the end, so the lowest ones go first
+        let free = (0..layout.frame_count)
+            .rev()
+            .map(|frame| layout.frame_base(frame))
+            .collect();
+        FramePool {
+            free: Mutex::new(free),
+            layout,
+        }
+    }
+
+    /// Returns the number of free frames.
+    pub fn len(&self) -> usize {
+        self.free.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Returns `true` if no frame is free.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes a free frame out of the pool.
+    ///
+    /// # Returns
+    /// The base address of the frame, or `None` if the pool is empty.
+    pub fn alloc(&self) -> Option<u64> {
+        self.free.lock().unwrap_or_else(|e| e.into_inner()).pop()
+    }
+
+    /// Takes up to `count` free frames out of the pool and appends their base
+    /// addresses to `frames`.
+    ///
+    /// # Returns
+    /// The number of frames taken, less than `count` if the pool runs out.
+    pub fn alloc_n(&self, count: usize, frames: &mut Vec<u64>) -> usize {
+        self.alloc_with(count, |addr| frames.push(addr))
+    }
+
+    /// Gives a frame back to the pool.
+    ///
+    /// `addr` may point anywhere into the frame, e.g. be the address of a received
+    /// packet. The caller must own the frame: it must not be in any ring nor be
+    /// freed twice.
+    ///
+    /// # Panics
+    /// Panics if `addr` is outside the UMEM, unless the `no_safety_checks` feature
+    /// is enabled.
+    pub fn free(&self, addr: u64) {
+        let base = self.base(addr);
+        self.free
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(base);
+    }
+
+    /// Calls `f` with up to `count` frames taken out of the pool and returns their
+    /// number.
+    pub(crate) fn alloc_with(&self, count: usize, mut f: impl FnMut(u64)) -> usize {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        let count = count.min(free.len());
+        let start = free.len() - count;
+        free.drain(start..).rev().for_each(&mut f);
+        count
+    }
+
+    /// Returns the base address of the frame holding `addr`.
+    fn base(&self, addr: u64) -> u64 {
+        let addr = self.layout.resolve(addr);
+        #[cfg(not(feature = "no_safety_checks"))]
+        assert!(addr < self.layout.umem_size() as u64);
+        addr - addr % self.layout.frame_size as u64
+    }
+}
+
+impl Socket<_TX> {
+    /// Transmits a frame allocated from the pool, without copying its data.
+    ///
+    /// The frame replaces the next free frame of the socket, found like `send` does
+    /// under the `Backpressure` policy, and that frame goes back to the pool. Once the
+    /// kernel has completed it, the frame is a free frame of the socket.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let addr = umem.pool().alloc().unwrap();
+    /// let len = build_packet(unsafe { umem.frame_mut(addr) });
+    /// tx.send_frame(addr, len)?;
+    /// ```
+    ///
+    /// # Arguments
+    /// * `addr` - An address within the frame, e.g. one returned by `FramePool::alloc`.
+    ///   The frame must be owned by the caller and is owned by the socket afterwards.
+    /// * `len` - The length of the packet data written with `Umem::frame_mut`.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or a `RingError` on failure.
+    ///
+    /// # Errors
+    /// Returns `RingError::InvalidLength` if `len` exceeds `FrameLayout::capacity`,
+    /// `RingError::NotAvailable` if the socket has no UMEM, and the errors of `send`
+    /// if no frame is free.
+    pub fn send_frame(&mut self, addr: u64, len: usize) -> Result<(), RingError> {
+        if len > self.layout.capacity() {
+            return Err(RingError::InvalidLength);
+        }
+        let umem = self
+            ._inner
+            .as_ref()
+            .ok_or(RingError::NotAvailable)?
+            .umem()
+            .clone();
+        self.reserve(len)?;
+        let x_head = self.producer & self.x_ring.mod_mask;
+        let parked = self.x_ring.desc_at(x_head).addr;
+        self.x_ring.set(&self.layout, x_head, addr, len as u32);
+        // a cookie set on the free frame belongs to the packet sent in its place
+        self.cookies.replace_frame(&self.layout, parked, addr);
+        self.owned.remove(parked);
+        self.owned.insert(addr);
+        umem.pool().free(parked);
+        self.commit_(1)
+    }
+}
+
+/// A set of UMEM frames, the frames owned by a socket.
+#[derive(Default)]
+pub(crate) struct FrameSet {
+    /// One bit per frame of the UMEM.
+    bits: Vec<u64>,
+    /// The number of frames in the set.
+    len: u32,
+    /// The geometry of the UMEM frames.
+    layout: FrameLayout,
+}
+
+impl FrameSet {
+    /// Creates a set holding the frames of the given addresses.
+    pub(crate) fn new(layout: FrameLayout, frames: &[u64]) -> Self {
+        let mut set = FrameSet {
+            bits: vec![0; (layout.frame_count as usize).div_ceil(64)],
+            len: 0,
+            layout,
+        };
+        frames.iter().for_each(|&addr| set.insert(addr));
+        set
+    }
+
+    /// Returns the number of frames in the set.
+    #[inline]
+    pub(crate) fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Adds the frame holding `addr` to the set.
+    #[inline]
+    pub(crate) fn insert(&mut self, addr: u64) {
+        let (word, bit) = self.position(addr);
+        if self.bits[word] & bit == 0 {
+            self.bits[word] |= bit;
+            self.len += 1;
+        }
+    }
+
+    /// Removes the frame holding `addr` from the set.
+    #[inline]
+    pub(crate) fn remove(&mut self, addr: u64) {
+        let (word, bit) = self.position(addr);
+        if self.bits[word] & bit != 0 {
+            self.bits[word] &= !bit;
+            self.len -= 1;
+        }
+    }
+
+    /// Empties the set and returns the base addresses of its frames.
+    pub(crate) fn take_all(&mut self) -> Vec<u64> {
+        let mut frames = Vec::with_capacity(self.len as usize);
+        for (word, bits) in self.bits.iter_mut().enumerate() {
+            while *bits != 0 {
+                let bit = bits.trailing_zeros();
+                *bits &= *bits - 1;
+                frames.push(self.layout.frame_base(word as u32 * 64 + bit));
+            }
+        }
+        self.len = 0;
+        frames
+    }
+
+    /// Returns the word index and the bit mask of the frame holding `addr`.
+    #[inline]
+    fn position(&self, addr: u64) -> (usize, u64) {
+        let frame = self.layout.resolve(addr) / self.layout.frame_size as u64;
+        ((frame / 64) as usize, 1 << (frame % 64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> FrameLayout {
+        FrameLayout {
+            frame_size: 2048,
+            frame_count: 100,
+            headroom: 0,
+            tx_metadata_len: 0,
+            unaligned: false,
+        }
+    }
+
+    #[test]
+    fn pool_allocates_and_frees_frames() {
+        let pool = FramePool::new(layout());
+        assert_eq!(pool.len(), 100);
+        assert_eq!(pool.alloc(), Some(0));
+        let mut frames = Vec::new();
+        assert_eq!(pool.alloc_n(3, &mut frames), 3);
+        assert_eq!(frames, [2048, 4096, 6144]);
+        assert_eq!(pool.len(), 96);
+
+        // any address within a frame gives back its base
+        pool.free(4096 + 300);
+        assert_eq!(pool.alloc(), Some(4096));
+
+        frames.clear();
+        assert_eq!(pool.alloc_n(200, &mut frames), 96);
+        assert!(pool.is_empty());
+        assert_eq!(pool.alloc(), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "no_safety_checks"))]
+    #[should_panic]
+    fn pool_rejects_foreign_frames() {
+        FramePool::new(layout()).free(100 * 2048);
+    }
+
+    #[test]
+    fn frame_set_tracks_owned_frames() {
+        let layout = layout();
+        let mut set = FrameSet::new(layout, &[0, 2048 + 256, 99 * 2048]);
+        assert_eq!(set.len(), 3);
+        set.insert(2048);
+        assert_eq!(set.len(), 3);
+        set.remove(99 * 2048 + 64);
+        set.remove(99 * 2048);
+        assert_eq!(set.len(), 2);
+        set.insert(70 * 2048);
+        assert_eq!(set.take_all(), [0, 2048, 70 * 2048]);
+        assert_eq!(set.len(), 0);
+        assert!(set.take_all().is_empty());
+    }
+}
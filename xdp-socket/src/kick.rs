@@ -8,38 +8,57 @@
 //!
 //! ## How it works
 //!
-//! The `kick` method checks the `XDP_RING_NEED_WAKEUP` flag in the ring's flags
-//! field. If set, it performs a zero-length `sendto` syscall to signal the kernel.
-//! This prompts the kernel to check the rings for new descriptors to process.
+//! The `kick` method checks the `XDP_RING_NEED_WAKEUP` flag of the ring the kernel
+//! consumes from: the TX ring for transmit sockets and the Fill ring for receive
+//! sockets. If set, or if the socket busy polls, it performs a zero-length `sendto`
+//! (TX) or `recvfrom` (RX) syscall to signal the kernel. This prompts the kernel to
+//! check the rings for new descriptors to process.
 //!
 //! ## Main components
 //!
+//! - `need_wakeup`: Checks whether the kernel is waiting for a wakeup call.
 //! - `kick`: Main method to trigger kernel wakeup for XDP socket rings.
+//! - `ring_needs_wakeup()`, `wake_up()`: The flag check and the syscall, shared with
+//!   `XdpPoller`.
 
 #![allow(private_interfaces)]
 #![allow(private_bounds)]
 
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::{io, ptr};
 
-use crate::socket::{_Direction, Commit_, RingError, Socket};
+use crate::socket::{_Direction, _RX, _TX, Commit_, RingError, Socket};
 
 /// Implements the kernel wakeup logic for `Socket`.
 impl<const T: _Direction> Socket<T>
 where
     Socket<T>: Commit_<T>,
 {
+    /// Returns `true` if the kernel has to be woken up to process the rings.
+    ///
+    /// For a `TxSocket` this checks the `XDP_RING_NEED_WAKEUP` flag of the TX ring,
+    /// for an `RxSocket` the flag of the Fill ring. The flag is only ever set when the
+    /// socket is bound with `XDP_USE_NEED_WAKEUP`.
+    #[inline]
+    pub fn need_wakeup(&self) -> bool {
+        match T {
+            _TX => ring_needs_wakeup(self.x_ring.mmap.flags),
+            _RX => ring_needs_wakeup(self.u_ring.mmap.flags),
+        }
+    }
+
     /// Wakes up the kernel to process descriptors in the rings.
     ///
     /// This method is used to notify the kernel that it needs to process packets,
     /// which is particularly important when the `XDP_USE_NEED_WAKEUP` flag is set
-    /// on the socket. It checks if the `XDP_RING_NEED_WAKEUP` flag is set in the
-    /// ring's flags field and, if so, performs a syscall to wake up the kernel.
+    /// on the socket. It checks if the kernel needs a wakeup with `need_wakeup`
+    /// and, if so, performs a syscall to wake up the kernel. A busy-polling socket
+    /// makes the syscall every time, as it drives the driver from this thread.
     ///
     /// # How it works
     ///
-    /// It performs a `sendto` syscall with a zero-length buffer. This syscall does not transfer
-    /// any data but acts as a signal to the kernel.
+    /// It performs a `sendto` (TX) or `recvfrom` (RX) syscall with a zero-length
+    /// buffer. This syscall does not transfer any data but acts as a signal to the kernel.
     ///
     /// # Returns
     ///
@@ -47,34 +66,8 @@ where
     /// for certain non-critical errors like `EBUSY` or `EAGAIN`. A warning is
     /// logged for `ENETDOWN`.
     pub fn kick(&self) -> Result<(), io::Error> {
-        let need_wakeup = unsafe {
-            (*self.x_ring.mmap.flags).load(Ordering::Relaxed) & libc::XDP_RING_NEED_WAKEUP != 0
-        };
-
-        if need_wakeup {
-            let ret = unsafe {
-                libc::sendto(
-                    self.raw_fd,
-                    ptr::null(),
-                    0,
-                    libc::MSG_DONTWAIT | libc::MSG_NOSIGNAL,
-                    ptr::null(),
-                    0,
-                )
-            };
-
-            if ret < 0 {
-                match io::Error::last_os_error().raw_os_error() {
-                    None | Some(libc::EBUSY | libc::ENOBUFS | libc::EAGAIN) => {}
-                    Some(libc::ENETDOWN) => {
-                        // TODO: better handling
-                        log::warn!("network interface is down, cannot wake up");
-                    }
-                    Some(e) => {
-                        return Err(io::Error::from_raw_os_error(e));
-                    }
-                }
-            }
+        if self.busy_poll || self.need_wakeup() {
+            wake_up(self.raw_fd, T)?;
         }
         Ok(())
     }
@@ -97,3 +90,51 @@ where
         self.kick().map_err(RingError::Io)
     }
 }
+
+/// Returns `true` if the `XDP_RING_NEED_WAKEUP` flag is set in the ring flags.
+#[inline]
+pub(crate) fn ring_needs_wakeup(flags: *mut AtomicU32) -> bool {
+    !flags.is_null()
+        && unsafe { (*flags).load(Ordering::Relaxed) & libc::XDP_RING_NEED_WAKEUP != 0 }
+}
+
+/// Performs the wakeup syscall for a socket of the given direction.
+///
+/// A zero-length `sendto` wakes up transmission, a zero-length `recvfrom` the
+/// processing of the Fill ring. Non-critical errors are ignored.
+pub(crate) fn wake_up(raw_fd: libc::c_int, direction: _Direction) -> Result<(), io::Error> {
+    let ret = unsafe {
+        match direction {
+            _TX => libc::sendto(
+                raw_fd,
+                ptr::null(),
+                0,
+                libc::MSG_DONTWAIT | libc::MSG_NOSIGNAL,
+                ptr::null(),
+                0,
+            ),
+            _RX => libc::recvfrom(
+                raw_fd,
+                ptr::null_mut(),
+                0,
+                libc::MSG_DONTWAIT,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            ),
+        }
+    };
+
+    if ret < 0 {
+        match io::Error::last_os_error().raw_os_error() {
+            None | Some(libc::EBUSY | libc::ENOBUFS | libc::EAGAIN) => {}
+            Some(libc::ENETDOWN) => {
+                // TODO: better handling
+                log::warn!("network interface is down, cannot wake up");
+            }
+            Some(e) => {
+                return Err(io::Error::from_raw_os_error(e));
+            }
+        }
+    }
+    Ok(())
+}
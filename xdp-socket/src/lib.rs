@@ -21,11 +21,43 @@
 //!
 //! - [`Socket`]: The main type representing an AF_XDP socket, parameterized by direction
 //!   (TX or RX). Provides methods for sending, receiving, and managing descriptors.
-//! - [`UMEM`]: User memory region for zero-copy packet buffers, shared with the kernel.
+//! - [`SocketBuilder`]: Chainable socket settings, validated up front, building TX,
+//!   RX or paired sockets; the `create_*` functions are thin wrappers around it.
+//! - [`Umem`]: User memory region for zero-copy packet buffers, shared with the kernel
+//!   and optionally with other sockets on different queues or interfaces.
+//! - [`FramePool`]: The free frames of a `Umem`, which sockets and the application
+//!   allocate frames from independently of ring positions. Allocated frames are written
+//!   with `Umem::frame_mut` and transmitted with `send_frame`.
 //! - Ring Buffers: Fill, Completion, TX, and RX rings for packet flow control and
 //!   synchronization with the kernel.
 //! - [`PollWaitExt`]: Trait for blocking until the socket is ready for I/O.
-//! - [`SendExt`]: Trait for high-level, ergonomic packet sending on transmit sockets.
+//! - [`XdpPoller`]: Waits on many sockets at once, e.g. one per NIC queue, from a
+//!   single thread.
+//! - [`XdpOptions`]: The bind mode of a socket, read with `Socket::options` to verify
+//!   that it runs in zero-copy mode.
+//! - [`TxMetadata`]: Checksum offload, TX timestamp and launch time requests written in
+//!   front of transmitted packets with `peek_metadata`.
+//! - [`TxCompletion`]: A frame completed by the kernel with its TX timestamp, yielded by
+//!   `Socket::completions`.
+//! - [`CompletedCookies`]: The user cookies attached to TX frames with `set_cookie`,
+//!   yielded once the kernel has completed them.
+//! - [`XdpStatistics`]: Drop and error counters of a socket, read with
+//!   `Socket::statistics`.
+//! - [`SendExt`]: Trait for high-level, ergonomic packet sending on transmit sockets,
+//!   finding free frames itself under a configurable [`Backpressure`] policy.
+//! - [`RxFrame`]: An owned received packet taken with `take_frame`, which can be kept
+//!   and handed to worker code; its frame goes back to the frame pool on drop, and the
+//!   socket refills its Fill ring from the pool.
+//! - [`RxBatch`]: A batch of received packets from `recv_batch`, iterated in one loop
+//!   and committed on drop. `send_batch` is its TX counterpart, queueing many packets
+//!   with a single commit and kick.
+//! - [`Fragments`]: Iterator over the fragments of a received multi-buffer packet, for
+//!   packets like jumbo frames spanning several UMEM frames.
+//! - `AsyncSocket`: Tokio `AsyncFd` wrapper with async `seek`, `send` and `recv`, and
+//!   `AsyncBiSocket` for both halves of a bidirectional socket, available with the
+//!   `tokio` feature.
+//! - `mio::event::Source`: Implemented for [`Socket`] with the `mio` feature, to register
+//!   sockets with custom event loops.
 //!
 //! ## Descriptor Flow: seek → peek → commit → kick
 //!
@@ -44,26 +76,74 @@
 //!
 
 // Public modules and re-exports
+pub mod builder;
+pub mod completion;
 pub mod create;
+pub mod frame_pool;
 pub mod mmap;
+pub mod options;
 pub mod ring;
 pub mod socket;
+pub mod umem;
 
+pub use batch::{RxBatch, RxBatchIter};
+pub use builder::SocketBuilder;
+pub use completion::{Completions, TxCompletion};
+pub use cookie::CompletedCookies;
 pub use create::{
-    Direction, XdpConfig, create_bi_socket, create_rx_socket, create_socket, create_tx_socket,
+    Direction, XdpConfig, create_bi_socket, create_bi_socket_by_name, create_rx_socket,
+    create_rx_socket_by_name, create_shared_socket, create_socket, create_socket_by_name,
+    create_tx_socket, create_tx_socket_by_name, if_index_by_name,
 };
+pub use frame_pool::FramePool;
+pub use multi_buffer::Fragments;
+pub use options::XdpOptions;
+pub use rx_frame::RxFrame;
 pub use socket::Socket;
+pub use tx_metadata::TxMetadata;
+pub use umem::Umem;
 
 // Internal modules, hidden from documentation
 #[doc(hidden)]
+pub mod adjust;
+#[doc(hidden)]
+pub mod batch;
+#[doc(hidden)]
+pub mod busy_poll;
+#[doc(hidden)]
 pub mod commit;
 #[doc(hidden)]
+pub mod cookie;
+#[doc(hidden)]
+pub mod forward;
+#[doc(hidden)]
 pub mod kick;
+#[doc(hidden)]
+pub mod multi_buffer;
 pub mod peek;
 pub mod poll;
+pub mod poller;
+#[doc(hidden)]
+pub mod rx_frame;
 #[doc(hidden)]
 pub mod seek;
 #[doc(hidden)]
 pub mod send;
+pub mod statistics;
+pub mod tx_metadata;
+
+pub use {
+    poll::PollWaitExt,
+    poller::XdpPoller,
+    send::{Backpressure, SendExt},
+    statistics::XdpStatistics,
+};
+
+#[cfg(feature = "tokio")]
+pub mod async_fd;
+
+#[cfg(feature = "tokio")]
+pub use async_fd::{AsyncBiSocket, AsyncRxSocket, AsyncSocket, AsyncTxSocket};
 
-pub use {poll::PollWaitExt, send::SendExt};
+#[cfg(feature = "mio")]
+pub mod event_source;
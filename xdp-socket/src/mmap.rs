@@ -118,6 +118,11 @@ impl OwnedMmap {
     }
 }
 
+// OwnedMmap only owns the mapping, access to the mapped memory is synchronized by
+//  its users, so it can be moved and shared between threads
+unsafe impl Send for OwnedMmap {}
+unsafe impl Sync for OwnedMmap {}
+
 impl Drop for OwnedMmap {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,270 @@
+//! # Multi-Buffer Packets
+//!
+//! ## Purpose
+//!
+//! This file implements multi-buffer packets, which let a packet larger than a UMEM frame,
+//! like a 9000-byte jumbo frame, span a chain of descriptors. It requires the socket to be
+//! bound with `XDP_USE_SG`, enabled by `XdpConfig::multi_buffer`.
+//!
+//! ## How it works
+//!
+//! Every descriptor of a chain except the last one has the `XDP_PKT_CONTD` option set,
+//! each of them pointing to its own frame holding a fragment of the packet.
+//!
+//! For `_RX`, `seek_packet` makes sure all fragments of the first received packet are
+//! available and returns their number, `peek_packet` iterates over the fragment data,
+//! and the fragments are released together with `commit_n`.
+//!
+//! For `_TX`, `write_packet_vectored` gathers a packet from several slices and splits
+//! it over as many available descriptors as needed, chaining them; the descriptors are
+//! then submitted with `commit_n`. `write_packet` is its header plus data variant. The
+//! `SendExt::send` and `SendExt::send_vectored` methods use them, so multi-buffer
+//! sockets can send jumbo frames directly.
+//!
+//! ## Main components
+//!
+//! - `impl Socket<_RX>`: Provides `seek_packet` and `peek_packet`.
+//! - `impl Socket<_TX>`: Provides `write_packet` and `write_packet_vectored`.
+//! - `Fragments`: An iterator over the fragments of a received packet.
+
+use crate::socket::{_RX, _TX, RingError, Seek_ as _, Socket};
+use std::io::IoSlice;
+
+impl Socket<_RX> {
+    /// Ensures that all fragments of the first received packet are available and
+    /// returns the number of descriptors the packet spans.
+    ///
+    /// A packet that fits in one frame spans a single descriptor. Once processed,
+    /// the packet is released with `commit_n` and the returned count.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of descriptors of the first packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::RingEmpty` if no complete packet has been received yet.
+    pub fn seek_packet(&mut self) -> Result<usize, RingError> {
+        loop {
+            if let Some(count) = self.packet_descs() {
+                return Ok(count);
+            }
+            let available = self.available as usize;
+            if self.seek_(available + 1)? <= available {
+                return Err(RingError::RingEmpty);
+            }
+        }
+    }
+
+    /// Returns an iterator over the fragments of the first available packet, after
+    /// `seek_packet` has made it available.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Fragments` of the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::NotAvailable` if the packet is not complete among the
+    /// available descriptors.
+    pub fn peek_packet(&self) -> Result<Fragments<'_>, RingError> {
+        let count = self.packet_descs().ok_or(RingError::NotAvailable)?;
+        Ok(Fragments {
+            socket: self,
+            index: 0,
+            count,
+        })
+    }
+
+    /// Returns the number of descriptors of the first packet, or `None` if its last
+    /// fragment is not available yet.
+    fn packet_descs(&self) -> Option<usize> {
+        (0..self.available).find_map(|i| {
+            let x_head = self.consumer.wrapping_add(i) & self.x_ring.mod_mask;
+            let options = self.x_ring.desc_at(x_head).options;
+            (options & libc::XDP_PKT_CONTD == 0).then_some(i as usize + 1)
+        })
+    }
+}
+
+/// An iterator over the fragments of a received multi-buffer packet.
+///
+/// Each item is the data of one fragment, in packet order.
+pub struct Fragments<'a> {
+    /// The socket the packet was received on.
+    socket: &'a Socket<_RX>,
+    /// The index of the next fragment in the range of available descriptors.
+    index: usize,
+    /// The number of descriptors of the packet.
+    count: usize,
+}
+
+impl<'a> Iterator for Fragments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index == self.count {
+            return None;
+        }
+        let socket = self.socket;
+        let x_head = socket.consumer.wrapping_add(self.index as u32) & socket.x_ring.mod_mask;
+        self.index += 1;
+        Some(
+            socket
+                .x_ring
+                .bytes_at(socket.frames, &socket.layout, x_head),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.count - self.index;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for Fragments<'_> {}
+
+impl Socket<_TX> {
+    /// Writes a packet into the available descriptors starting at `index`, chaining
+    /// as many of them as the packet needs.
+    ///
+    /// The packet is the optional `header` followed by `data`. It is split into
+    /// fragments filling the room left in each frame, at most `frame_capacity`
+    /// bytes, and every descriptor except the
+    /// last one is marked with `XDP_PKT_CONTD`. The caller must make enough
+    /// descriptors available with `seek_n` beforehand, and submit the returned
+    /// number of descriptors with `commit_n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first descriptor in the range of available ones.
+    /// * `data` - A byte slice containing the packet payload.
+    /// * `header` - An optional byte slice for the packet header.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of descriptors used by the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidLength` if the packet does not fit in one frame and
+    /// the socket is not a multi-buffer socket, and `RingError::NotAvailable` if fewer
+    /// descriptors are available than the packet needs.
+    pub fn write_packet(
+        &mut self,
+        index: usize,
+        data: &[u8],
+        header: Option<&[u8]>,
+    ) -> Result<usize, RingError> {
+        let header = header.unwrap_or_default();
+        self.write_packet_vectored(index, &[IoSlice::new(header), IoSlice::new(data)])
+    }
+
+    /// Writes a packet gathered from several slices into the available descriptors
+    /// starting at `index`, chaining as many of them as the packet needs.
+    ///
+    /// The packet is the concatenation of `bufs`, e.g. the Ethernet, IP and UDP
+    /// headers, an application header and payload chunks, copied straight into the
+    /// UMEM without a temporary buffer. See `write_packet` for how the packet is
+    /// split into fragments.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the first descriptor in the range of available ones.
+    /// * `bufs` - The slices making up the packet, in order.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of descriptors used by the packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::InvalidLength` if the packet does not fit in one frame and
+    /// the socket is not a multi-buffer socket, and `RingError::NotAvailable` if fewer
+    /// descriptors are available than the packet needs.
+    pub fn write_packet_vectored(
+        &mut self,
+        index: usize,
+        bufs: &[IoSlice<'_>],
+    ) -> Result<usize, RingError> {
+        let total = bufs.iter().map(|buf| buf.len()).sum::<usize>();
+        // descriptors moved with adjust_head have less room left in their frame
+        let mut count = 0;
+        let mut room = 0;
+        while count == 0 || room < total {
+            if count == 1 && !self.multi_buffer {
+                return Err(RingError::InvalidLength);
+            }
+            #[cfg(not(feature = "no_safety_checks"))]
+            if index + count >= self.available as usize {
+                return Err(RingError::NotAvailable);
+            }
+            let x_head = self.producer.wrapping_add((index + count) as u32) & self.x_ring.mod_mask;
+            room += self.layout.room(self.x_ring.desc_at(x_head).addr);
+            count += 1;
+        }
+        let mut offset = 0;
+        for i in 0..count {
+            let x_head = self.producer.wrapping_add((index + i) as u32) & self.x_ring.mod_mask;
+            let len = (total - offset).min(self.layout.room(self.x_ring.desc_at(x_head).addr));
+            let buf = self
+                .x_ring
+                .mut_bytes_at(self.frames, &self.layout, x_head, len);
+            copy_gather(buf, offset, bufs);
+            let options = &mut self.x_ring.mut_desc_at(x_head).options;
+            // keep a TX metadata request made with peek_metadata
+            *options &= !libc::XDP_PKT_CONTD;
+            if i + 1 < count {
+                *options |= libc::XDP_PKT_CONTD;
+            }
+            offset += len;
+        }
+        Ok(count)
+    }
+}
+
+/// Fills `buf` with the bytes of the concatenation of `bufs`, starting at `offset`
+/// of the concatenation.
+fn copy_gather(buf: &mut [u8], mut offset: usize, bufs: &[IoSlice<'_>]) {
+    let mut written = 0;
+    for src in bufs {
+        if written == buf.len() {
+            break;
+        }
+        if offset >= src.len() {
+            offset -= src.len();
+            continue;
+        }
+        let len = (src.len() - offset).min(buf.len() - written);
+        buf[written..written + len].copy_from_slice(&src[offset..offset + len]);
+        written += len;
+        offset = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_gather_splits_slices_over_frames() {
+        let bufs = [
+            IoSlice::new(b"abc"),
+            IoSlice::new(b""),
+            IoSlice::new(b"defgh"),
+            IoSlice::new(b"ij"),
+        ];
+        let mut frames = [[0u8; 4]; 3];
+        for (i, frame) in frames.iter_mut().enumerate() {
+            copy_gather(frame, i * 4, &bufs);
+        }
+        assert_eq!(&frames[0], b"abcd");
+        assert_eq!(&frames[1], b"efgh");
+        assert_eq!(&frames[2][..2], b"ij");
+        assert_eq!(&frames[2][2..], [0, 0]);
+
+        let mut whole = [0u8; 10];
+        copy_gather(&mut whole, 0, &bufs);
+        assert_eq!(&whole, b"abcdefghij");
+    }
+}
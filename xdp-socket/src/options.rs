@@ -0,0 +1,89 @@
+//! # XDP Socket Bind Options
+//!
+//! ## Purpose
+//!
+//! This file implements the `options` and `is_zero_copy` methods for the `Socket`. When
+//! `XdpConfig::zero_copy` is `None`, the kernel picks zero-copy or copy mode on its own;
+//! these methods report the mode the socket was actually bound in.
+//!
+//! ## How it works
+//!
+//! The options are read with `getsockopt(SOL_XDP, XDP_OPTIONS)`, which returns the
+//! `XDP_OPTIONS_*` flags of a bound socket. The same query backs
+//! `XdpConfig::require_zero_copy`, which makes socket creation fail when the socket
+//! ends up in copy mode.
+//!
+//! ## Main components
+//!
+//! - `XdpOptions`: The option flags of a bound socket.
+//! - `options()`, `is_zero_copy()`: Query the options of a socket.
+//! - `xdp_options()`: The underlying `getsockopt` call on a raw file descriptor.
+
+#![allow(non_upper_case_globals)]
+
+use crate::socket::{_Direction, Socket};
+use std::io;
+use std::mem::size_of;
+
+/// The options of a bound AF_XDP socket.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct XdpOptions {
+    /// The raw `XDP_OPTIONS_*` flags returned by the kernel.
+    pub flags: u32,
+}
+
+impl XdpOptions {
+    /// Returns `true` if the socket is bound in zero-copy mode.
+    #[inline]
+    pub fn is_zero_copy(&self) -> bool {
+        self.flags & libc::XDP_OPTIONS_ZEROCOPY != 0
+    }
+}
+
+impl<const t: _Direction> Socket<t> {
+    /// Reads the options of the bound socket from the kernel.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `XdpOptions` of the socket, or an `io::Error` if
+    /// `getsockopt` fails.
+    #[inline]
+    pub fn options(&self) -> io::Result<XdpOptions> {
+        xdp_options(self.raw_fd)
+    }
+
+    /// Returns `true` if the socket is bound in zero-copy mode, `false` if the kernel
+    /// copies packets between the driver and the UMEM.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if `getsockopt` fails.
+    #[inline]
+    pub fn is_zero_copy(&self) -> io::Result<bool> {
+        Ok(self.options()?.is_zero_copy())
+    }
+}
+
+/// Reads the options of a bound AF_XDP socket with `getsockopt(XDP_OPTIONS)`.
+///
+/// # Arguments
+/// * `raw_fd` - The raw file descriptor of the AF_XDP socket.
+pub fn xdp_options(raw_fd: libc::c_int) -> io::Result<XdpOptions> {
+    let mut options = libc::xdp_options { flags: 0 };
+    let mut optlen = size_of::<libc::xdp_options>() as libc::socklen_t;
+    unsafe {
+        if libc::getsockopt(
+            raw_fd,
+            libc::SOL_XDP,
+            libc::XDP_OPTIONS,
+            &mut options as *mut _ as *mut libc::c_void,
+            &mut optlen,
+        ) < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(XdpOptions {
+        flags: options.flags,
+    })
+}
@@ -26,7 +26,8 @@
 //! - `impl Socket<_TX>`: Provides `peek`, `peek_at`, and `seek_and_peek` for
 //!   transmit sockets.
 //! - `impl Socket<_RX>`: Provides `peek`, `peek_at`, and `seek_and_peek` for
-//!   receive sockets.
+//!   receive sockets, and `peek_mut`, `peek_mut_at` to modify received packets
+//!   in place.
 
 #![allow(private_interfaces)]
 #![allow(private_bounds)]
@@ -55,13 +56,16 @@ where
         if index >= self.available as usize {
             return Err(RingError::InvalidIndex);
         }
+        let x_head = self.producer.wrapping_add(index as u32) & self.x_ring.mod_mask;
         #[cfg(not(feature = "no_safety_checks"))]
-        if len > self.x_ring.frame_size() as usize {
+        if len > self.layout.room(self.x_ring.desc_at(x_head).addr) {
             return Err(RingError::InvalidLength);
         }
-        let x_head = self.producer.wrapping_add(index as u32) & self.x_ring.mod_mask;
-        self.x_ring.mut_desc_at(x_head).len = len as u32;
-        Ok(self.x_ring.mut_bytes_at(self.frames, x_head, len))
+        // a single frame packet, drop a chain flag left by a previous write
+        self.x_ring.mut_desc_at(x_head).options &= !libc::XDP_PKT_CONTD;
+        Ok(self
+            .x_ring
+            .mut_bytes_at(self.frames, &self.layout, x_head, len))
     }
 
     /// Peeks at the next available chunk in the ring without advancing the head.
@@ -133,15 +137,17 @@ where
     ///
     /// # Returns
     ///
-    /// A `Result` containing a byte slice and its corresponding descriptor index.
-    fn peek_(&mut self, index: usize) -> Result<&[u8], RingError> {
+    /// A `Result` containing a mutable byte slice of the received packet.
+    fn peek_(&mut self, index: usize) -> Result<&mut [u8], RingError> {
         #[cfg(not(feature = "no_safety_checks"))]
         if index >= self.available as usize {
             return Err(RingError::InvalidIndex);
         }
         let x_head = self.consumer.wrapping_add(index as u32) & self.x_ring.mod_mask;
         let len = self.x_ring.desc_at(x_head).len as usize;
-        Ok(self.x_ring.mut_bytes_at(self.frames, x_head, len))
+        Ok(self
+            .x_ring
+            .mut_bytes_at(self.frames, &self.layout, x_head, len))
     }
     /// Peeks at the first available chunk in the ring without advancing the head.
     ///
@@ -153,7 +159,7 @@ where
     /// A `Result` containing a byte slice and its corresponding descriptor index.
     #[inline]
     pub fn peek(&mut self) -> Result<&[u8], RingError> {
-        self.peek_(0)
+        Ok(self.peek_(0)?)
     }
 
     /// Peeks at the `index`-th available chunk in the ring without advancing the head.
@@ -170,7 +176,7 @@ where
     /// A `Result` containing a byte slice and its corresponding descriptor index.
     #[inline]
     pub fn peek_at(&mut self, index: usize) -> Result<&[u8], RingError> {
-        self.peek_(index)
+        Ok(self.peek_(index)?)
     }
 
     /// Seeks to the next available descriptor in the ring and peeks at the descriptor
@@ -184,6 +190,33 @@ where
     /// A `Result` containing a byte slice and its corresponding descriptor index.
     pub fn seek_and_peek(&mut self) -> Result<&[u8], RingError> {
         self.seek_(1)?;
+        Ok(self.peek_(0)?)
+    }
+
+    /// Peeks at the first available chunk in the ring for in-place modification.
+    ///
+    /// This is useful together with `forward`, to rewrite headers of a received
+    /// packet before handing its frame to a TX ring.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a mutable byte slice of the received packet.
+    #[inline]
+    pub fn peek_mut(&mut self) -> Result<&mut [u8], RingError> {
         self.peek_(0)
     }
+
+    /// Peeks at the `index`-th available chunk in the ring for in-place modification.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index in the range of available descriptors.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a mutable byte slice of the received packet.
+    #[inline]
+    pub fn peek_mut_at(&mut self, index: usize) -> Result<&mut [u8], RingError> {
+        self.peek_(index)
+    }
 }
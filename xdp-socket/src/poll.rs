@@ -12,11 +12,15 @@
 //! ## How it works
 //!
 //! The `poll_wait` method blocks the current thread until the socket's file descriptor
-//! becomes ready for I/O. It uses `poll` to wait for the socket's readiness event,
-//! which depends on the socket direction:
+//! becomes ready for I/O or the timeout expires. It uses `poll` to wait for the socket's
+//! readiness event, which depends on the socket direction:
 //! - For transmit sockets (`_TX`), it waits for the socket to be writable (`POLLOUT`).
 //! - For receive sockets (`_RX`), it waits for the socket to be readable (`POLLIN`).
 //!
+//! An expired timeout is reported as an `io::Error` of kind `TimedOut`. Interrupted
+//! `poll` calls are restarted with the remaining time, while other `poll` failures and
+//! `POLLERR`, `POLLHUP` or `POLLNVAL` events are returned as errors.
+//!
 //! ## Main components
 //!
 //! - `impl PollWait<_TX>`: An implementation block for the transmit socket.
@@ -28,12 +32,14 @@
 
 use crate::socket::{_Direction, _RX, _TX, Commit_, Socket};
 use std::io;
-use std::time::Duration;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
 
 /// A trait for polling XDP sockets for readiness events.
 ///
 /// This trait provides the `poll_wait` method, which blocks until the socket's file
-/// descriptor becomes ready for I/O. The readiness event depends on the socket direction:
+/// descriptor becomes ready for I/O or the timeout expires. The readiness event depends
+/// on the socket direction:
 /// - For transmit sockets (`_TX`), it waits for the socket to be writable (`POLLOUT`).
 /// - For receive sockets (`_RX`), it waits for the socket to be readable (`POLLIN`).
 ///
@@ -46,17 +52,22 @@ use std::time::Duration;
 /// ```rust
 /// use xdp_socket::{ create_socket, PollWaitExt as _ } ;
 /// let socket = ...; // your Socket<_TX> or Socket<_RX>
-/// socket.poll_wait(Some(std::time::Duration::from_secs(1)))?;
+/// match socket.poll_wait(Some(std::time::Duration::from_secs(1))) {
+///     Ok(()) => { /* ready */ }
+///     Err(e) if e.kind() == std::io::ErrorKind::TimedOut => { /* deadline passed */ }
+///     Err(e) => return Err(e),
+/// }
 /// ```
 pub trait PollWaitExt<const t: _Direction> {
-    fn poll_wait(&self, _timeout: Option<Duration>) -> Result<(), io::Error>;
+    fn poll_wait(&self, timeout: Option<Duration>) -> Result<(), io::Error>;
 }
 
 impl<const t: _Direction> PollWaitExt<t> for Socket<t>
 where
     Socket<t>: Commit_<t>,
 {
-    /// Waits for the socket to become ready for I/O, blocking until an event occurs.
+    /// Waits for the socket to become ready for I/O, blocking until an event occurs
+    /// or the timeout expires.
     ///
     /// This function uses `poll` to wait for the socket's file descriptor to become
     /// ready. For a `TxSocket`, it waits for `POLLOUT` (writable). For an `RxSocket`,
@@ -64,31 +75,96 @@ where
     ///
     /// # Arguments
     ///
-    /// * `_timeout` - An optional timeout. If `None`, it blocks indefinitely.
+    /// * `timeout` - An optional timeout. If `None`, it blocks indefinitely. A zero
+    ///   timeout only checks the current readiness.
     ///
     /// # Returns
     ///
-    /// An `io::Result` indicating success or failure.
-    fn poll_wait(&self, _timeout: Option<Duration>) -> Result<(), io::Error> {
+    /// `Ok(())` once the socket is ready.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` of kind `TimedOut` if the timeout expires first. Errors
+    /// from `kick` and `poll` are returned as is, except `EINTR` which restarts the
+    /// wait. A pending socket error (`POLLERR`), a hang-up (`POLLHUP`) or an invalid
+    /// descriptor (`POLLNVAL`) are reported as errors as well.
+    fn poll_wait(&self, timeout: Option<Duration>) -> Result<(), io::Error> {
         self.kick()?;
         let mask = match t {
             _TX => libc::POLLOUT,
             _RX => libc::POLLIN,
         };
-        unsafe {
-            loop {
-                let mut fds = [libc::pollfd {
-                    events: mask,
-                    revents: 0,
-                    fd: self.raw_fd,
-                }];
-                if 0 > libc::poll(fds.as_mut_ptr(), 1, -1) {
-                    //..
-                } else if (fds[0].revents & mask) != 0 {
-                    break;
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            let wait_ms = timeout_ms(deadline);
+            let mut fds = [libc::pollfd {
+                events: mask,
+                revents: 0,
+                fd: self.raw_fd,
+            }];
+            let ret = unsafe { libc::poll(fds.as_mut_ptr(), 1, wait_ms) };
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
                 }
+                return Err(err);
+            }
+            if ret == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Timed out waiting for XDP socket",
+                ));
+            }
+            let revents = fds[0].revents;
+            if revents & libc::POLLNVAL != 0 {
+                return Err(io::Error::from_raw_os_error(libc::EBADF));
+            }
+            if revents & libc::POLLERR != 0 {
+                return Err(socket_error(self.raw_fd));
+            }
+            if revents & mask != 0 {
+                return Ok(());
+            }
+            if revents & libc::POLLHUP != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "XDP socket hung up",
+                ));
             }
         }
-        Ok(())
+    }
+}
+
+/// Returns the `poll` timeout in milliseconds left until `deadline`, rounded up so
+/// the wait never ends early, or `-1` to wait indefinitely.
+pub(crate) fn timeout_ms(deadline: Option<Instant>) -> libc::c_int {
+    deadline.map_or(-1, |deadline| {
+        let left = deadline.saturating_duration_since(Instant::now());
+        left.as_micros()
+            .div_ceil(1000)
+            .min(libc::c_int::MAX as u128) as libc::c_int
+    })
+}
+
+/// Reads and clears the pending error of a socket with `SO_ERROR`.
+fn socket_error(raw_fd: libc::c_int) -> io::Error {
+    let mut error: libc::c_int = 0;
+    let mut optlen = size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            raw_fd,
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            &mut error as *mut _ as *mut libc::c_void,
+            &mut optlen,
+        )
+    };
+    if ret < 0 {
+        io::Error::last_os_error()
+    } else if error != 0 {
+        io::Error::from_raw_os_error(error)
+    } else {
+        io::Error::other("XDP socket reported an error")
     }
 }
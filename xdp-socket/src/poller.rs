@@ -0,0 +1,303 @@
+//! # Multi-Socket Poller
+//!
+//! ## Purpose
+//!
+//! This file provides `XdpPoller`, which waits on many XDP sockets at once. Running one
+//! socket per NIC queue requires waiting on all of them simultaneously, which
+//! `PollWaitExt::poll_wait` cannot do as it only handles a single socket. With the
+//! poller, a single thread can service all queues of an interface.
+//!
+//! ## How it works
+//!
+//! Sockets are registered with a caller-chosen token in an `epoll` instance, waiting for
+//! `EPOLLOUT` on transmit sockets and `EPOLLIN` on receive sockets. The two halves of a
+//! bidirectional socket share one file descriptor, so their interests are merged into a
+//! single registration. Each entry keeps the ring mapping holding the
+//! `XDP_RING_NEED_WAKEUP` flag alive, so before every wait the poller wakes up the kernel
+//! for the sockets that need it or busy poll, exactly like `kick` does. After the wait,
+//! the tokens of the ready sockets are returned; they are then serviced with the
+//! regular `seek`/`peek`/`commit` API.
+//!
+//! ## Main components
+//!
+//! - `XdpPoller`: The `epoll` instance and the registered sockets.
+//! - `add()`, `remove()`: Register and deregister a socket under a token.
+//! - `wait()`: Kicks the sockets needing a wakeup and waits for ready sockets.
+
+#![allow(private_interfaces)]
+#![allow(private_bounds)]
+#![allow(non_upper_case_globals)]
+
+use crate::kick::{ring_needs_wakeup, wake_up};
+use crate::mmap::OwnedMmap;
+use crate::poll::timeout_ms;
+use crate::socket::{_Direction, _RX, _TX, Commit_, Inner, Socket};
+use std::io;
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+use std::sync::Arc;
+use std::sync::atomic::AtomicU32;
+use std::time::{Duration, Instant};
+
+/// Waits for readiness events on many XDP sockets at once.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use xdp_socket::{create_rx_socket, XdpPoller};
+/// let mut sockets = (0..4)
+///     .map(|queue| create_rx_socket(if_index, queue, None))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// let mut poller = XdpPoller::new()?;
+/// for (token, socket) in sockets.iter().enumerate() {
+///     poller.add(socket, token)?;
+/// }
+/// let mut ready = Vec::new();
+/// loop {
+///     poller.wait(&mut ready, None)?;
+///     for &token in &ready {
+///         let rx = &mut sockets[token];
+///         while rx.seek().is_ok() {
+///             let packet = rx.peek()?;
+///             // ... process the packet ...
+///             rx.commit()?;
+///         }
+///     }
+/// }
+/// ```
+pub struct XdpPoller {
+    /// The `epoll` instance the sockets are registered with.
+    epoll: OwnedFd,
+    /// The registered sockets.
+    entries: Vec<Entry>,
+    /// The buffer receiving the events from `epoll_wait`.
+    events: Vec<libc::epoll_event>,
+}
+
+/// A socket registered with an `XdpPoller`.
+struct Entry {
+    /// The token reported when the socket is ready.
+    token: usize,
+    /// The file descriptor of the socket.
+    raw_fd: libc::c_int,
+    /// The direction of the socket.
+    direction: _Direction,
+    /// The flags of the ring the kernel consumes from: TX for TX, Fill for RX.
+    flags: *mut AtomicU32,
+    /// Whether the socket busy polls and must be woken up before every wait.
+    busy_poll: bool,
+    /// The mapping of that ring, kept alive while the socket is registered.
+    _ring: Arc<OwnedMmap>,
+    /// The socket state, keeping its file descriptor open while it is registered.
+    _inner: Option<Arc<Inner>>,
+}
+
+// the ring flags are only read atomically, and their mapping is owned by the entry
+unsafe impl Send for XdpPoller {}
+
+impl XdpPoller {
+    /// Creates a poller without any registered socket.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `XdpPoller`, or an `io::Error` if the `epoll`
+    /// instance cannot be created.
+    pub fn new() -> io::Result<Self> {
+        let fd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(XdpPoller {
+            epoll: unsafe { OwnedFd::from_raw_fd(fd) },
+            entries: Vec::new(),
+            events: Vec::new(),
+        })
+    }
+
+    /// Returns the number of registered sockets.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no socket is registered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Registers a socket under the given token.
+    ///
+    /// The poller keeps the socket's file descriptor and ring mapping alive until the
+    /// socket is removed or the poller is dropped, so a dropped socket that is still
+    /// registered may keep being reported as ready.
+    ///
+    /// # Arguments
+    ///
+    /// * `socket` - The transmit or receive socket to register.
+    /// * `token` - The value reported by `wait` when the socket is ready.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` of kind `AlreadyExists` if the token is already in use,
+    /// or the error of `epoll_ctl` if the registration fails.
+    pub fn add<const t: _Direction>(&mut self, socket: &Socket<t>, token: usize) -> io::Result<()>
+    where
+        Socket<t>: Commit_<t>,
+    {
+        if self.entries.iter().any(|entry| entry.token == token) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("token {token} is already registered"),
+            ));
+        }
+        let (flags, ring) = match t {
+            _TX => (socket.x_ring.mmap.flags, &socket.x_ring.mmap.mmap),
+            _RX => (socket.u_ring.mmap.flags, &socket.u_ring.mmap.mmap),
+        };
+        let registered = self.is_registered(socket.raw_fd);
+        self.entries.push(Entry {
+            token,
+            raw_fd: socket.raw_fd,
+            direction: t,
+            flags,
+            busy_poll: socket.busy_poll,
+            _ring: ring.clone(),
+            _inner: socket._inner.clone(),
+        });
+        let op = if registered {
+            libc::EPOLL_CTL_MOD
+        } else {
+            libc::EPOLL_CTL_ADD
+        };
+        self.update(op, socket.raw_fd).inspect_err(|_| {
+            self.entries.pop();
+        })
+    }
+
+    /// Deregisters the socket registered under the given token.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The token the socket was registered with.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` on success.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` of kind `NotFound` if no socket is registered under the
+    /// token, or the error of `epoll_ctl` if the deregistration fails.
+    pub fn remove(&mut self, token: usize) -> io::Result<()> {
+        let Some(pos) = self.entries.iter().position(|entry| entry.token == token) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("token {token} is not registered"),
+            ));
+        };
+        let entry = self.entries.remove(pos);
+        let op = if self.is_registered(entry.raw_fd) {
+            libc::EPOLL_CTL_MOD
+        } else {
+            libc::EPOLL_CTL_DEL
+        };
+        self.update(op, entry.raw_fd)
+    }
+
+    /// Wakes up the kernel for the sockets that need it and waits until at least one
+    /// registered socket is ready or the timeout expires.
+    ///
+    /// A socket with a pending error or hang-up is reported as ready as well, so the
+    /// error surfaces when it is serviced.
+    ///
+    /// # Arguments
+    ///
+    /// * `ready` - Cleared and filled with the tokens of the ready sockets.
+    /// * `timeout` - An optional timeout. If `None`, it blocks indefinitely. A zero
+    ///   timeout only checks the current readiness.
+    ///
+    /// # Returns
+    ///
+    /// The number of ready sockets, `0` if the timeout expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if a wakeup or `epoll_wait` fails. Interrupted waits are
+    /// restarted with the remaining time.
+    pub fn wait(&mut self, ready: &mut Vec<usize>, timeout: Option<Duration>) -> io::Result<usize> {
+        ready.clear();
+        for entry in &self.entries {
+            if entry.busy_poll || ring_needs_wakeup(entry.flags) {
+                wake_up(entry.raw_fd, entry.direction)?;
+            }
+        }
+        let capacity = self.entries.len().max(1);
+        self.events
+            .resize(capacity, libc::epoll_event { events: 0, u64: 0 });
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let count = loop {
+            let ret = unsafe {
+                libc::epoll_wait(
+                    self.epoll.as_raw_fd(),
+                    self.events.as_mut_ptr(),
+                    capacity as libc::c_int,
+                    timeout_ms(deadline),
+                )
+            };
+            if ret >= 0 {
+                break ret as usize;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        };
+        for event in &self.events[..count] {
+            let (bits, raw_fd) = (event.events, event.u64 as libc::c_int);
+            for entry in self.entries.iter().filter(|entry| entry.raw_fd == raw_fd) {
+                let mask = interest(entry.direction) | (libc::EPOLLERR | libc::EPOLLHUP) as u32;
+                if bits & mask != 0 {
+                    ready.push(entry.token);
+                }
+            }
+        }
+        Ok(ready.len())
+    }
+
+    /// Returns `true` if any registered socket uses the file descriptor.
+    fn is_registered(&self, raw_fd: libc::c_int) -> bool {
+        self.entries.iter().any(|entry| entry.raw_fd == raw_fd)
+    }
+
+    /// Applies an `epoll_ctl` operation for the file descriptor, with the union of the
+    /// interests of the sockets registered on it.
+    fn update(&self, op: libc::c_int, raw_fd: libc::c_int) -> io::Result<()> {
+        let events = self
+            .entries
+            .iter()
+            .filter(|entry| entry.raw_fd == raw_fd)
+            .fold(0, |events, entry| events | interest(entry.direction));
+        let mut event = libc::epoll_event {
+            events,
+            u64: raw_fd as u64,
+        };
+        let ret = unsafe { libc::epoll_ctl(self.epoll.as_raw_fd(), op, raw_fd, &mut event) };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `epoll` readiness interest matching the socket direction.
+#[inline]
+fn interest(direction: _Direction) -> u32 {
+    match direction {
+        _TX => libc::EPOLLOUT as u32,
+        _RX => libc::EPOLLIN as u32,
+    }
+}
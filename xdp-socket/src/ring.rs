@@ -21,25 +21,141 @@
 //! - `RingMmap<T>`: A struct holding the raw memory-mapped components of a ring.
 //! - `XdpDesc`: The descriptor structure for packets in the TX and RX rings, containing
 //!   address, length, and options.
+//! - `FrameLayout`: The runtime geometry (frame size, frame count, headroom) of the UMEM.
 //! - `RingType`: An enum to differentiate between ring types and handle their specific
 //!   setup requirements.
 
 use crate::mmap::OwnedMmap;
+use std::sync::Arc;
 use std::sync::atomic::AtomicU32;
 use std::{io, mem::size_of, ptr, slice};
 
-/// The size of a single frame in the UMEM, typically 2KB or 4KB.
+/// The default size of a single frame in the UMEM, typically 2KB or 4KB.
 pub const FRAME_SIZE: usize = 2048;
 /// The default number of frames to allocate for the UMEM.
 pub const FRAME_COUNT: usize = 4096;
 
+/// The runtime geometry of the UMEM frames.
+///
+/// Every ring of a socket shares the same layout, which is fixed when the UMEM is
+/// registered with the kernel. Frame addresses, safety checks and the usable length
+/// of a frame are all derived from it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameLayout {
+    /// The size of a single frame in bytes, 2048 or 4096, or any size in between in
+    /// unaligned mode.
+    pub frame_size: u32,
+    /// The number of frames in the UMEM.
+    pub frame_count: u32,
+    /// The number of bytes reserved at the start of every frame before packet data.
+    pub headroom: u32,
+    /// The number of bytes of TX metadata placed right before transmitted packet data.
+    pub tx_metadata_len: u32,
+    /// Whether the UMEM is registered with `XDP_UMEM_UNALIGNED_CHUNK_FLAG`, so
+    /// descriptor addresses may carry an offset in their upper 16 bits.
+    pub unaligned: bool,
+}
+
+impl Default for FrameLayout {
+    fn default() -> Self {
+        FrameLayout {
+            frame_size: FRAME_SIZE as u32,
+            frame_count: FRAME_COUNT as u32,
+            headroom: 0,
+            tx_metadata_len: 0,
+            unaligned: false,
+        }
+    }
+}
+
+impl FrameLayout {
+    /// Returns the total size of the UMEM in bytes.
+    #[inline]
+    pub fn umem_size(&self) -> usize {
+        self.frame_size as usize * self.frame_count as usize
+    }
+
+    /// Returns the UMEM offset of the first byte of the given frame.
+    #[inline]
+    pub fn frame_base(&self, frame: u32) -> u64 {
+        frame as u64 * self.frame_size as u64
+    }
+
+    /// Returns the UMEM offset where packet data of the given frame starts,
+    /// i.e. the frame base shifted by the headroom and the TX metadata area.
+    #[inline]
+    pub fn frame_addr(&self, frame: u32) -> u64 {
+        self.frame_base(frame) + self.data_offset()
+    }
+
+    /// Returns the data address of the frame containing the given UMEM offset.
+    ///
+    /// Received packets are placed by the kernel after its own headroom, so RX
+    /// addresses point into the middle of a frame; this maps them back to the
+    /// address a TX descriptor of the same frame starts at.
+    #[inline]
+    pub fn data_addr(&self, addr: u64) -> u64 {
+        let addr = self.resolve(addr);
+        addr - addr % self.frame_size as u64 + self.data_offset()
+    }
+
+    /// Returns the UMEM offset a descriptor address points to.
+    ///
+    /// In unaligned mode the kernel reports received packets as the Fill ring
+    /// address with the packet offset in the upper 16 bits; this adds both parts.
+    /// Aligned addresses are returned unchanged.
+    #[inline]
+    pub fn resolve(&self, addr: u64) -> u64 {
+        if self.unaligned {
+            (addr & libc::XSK_UNALIGNED_BUF_ADDR_MASK)
+                + (addr >> libc::XSK_UNALIGNED_BUF_OFFSET_SHIFT)
+        } else {
+            addr
+        }
+    }
+
+    /// Returns the address to post to the Fill ring for the frame holding `addr`.
+    ///
+    /// The kernel masks Fill ring addresses to their chunk in aligned mode, but uses
+    /// them as is in unaligned mode, so they are moved back to the frame base.
+    #[inline]
+    pub fn fill_addr(&self, addr: u64) -> u64 {
+        if self.unaligned {
+            let addr = self.resolve(addr);
+            addr - addr % self.frame_size as u64
+        } else {
+            addr
+        }
+    }
+
+    /// Returns the number of bytes from `addr` to the end of its frame.
+    #[inline]
+    pub fn room(&self, addr: u64) -> usize {
+        (self.frame_size as u64 - self.resolve(addr) % self.frame_size as u64) as usize
+    }
+
+    /// Returns the number of bytes available for transmitted packet data in a
+    /// single frame.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.frame_size as usize - self.data_offset() as usize
+    }
+
+    /// Returns the offset of transmitted packet data from the frame base.
+    #[inline]
+    pub fn data_offset(&self) -> u64 {
+        self.headroom as u64 + self.tx_metadata_len as u64
+    }
+}
+
 /// Holds the raw memory-mapped components of a ring buffer.
 ///
 /// This struct contains raw pointers to the producer/consumer indices, the descriptor
 /// array, and flags within the memory-mapped region. It is managed by the `Ring` struct.
 pub struct RingMmap<T> {
-    /// The memory-mapped region owned by this struct.
-    pub mmap: OwnedMmap,
+    /// The memory-mapped region, shared with `XdpPoller` entries that watch the
+    /// ring flags.
+    pub mmap: Arc<OwnedMmap>,
     /// A pointer to the atomic producer index of the ring.
     pub producer: *mut AtomicU32,
     /// A pointer to the atomic consumer index of the ring.
@@ -53,7 +169,7 @@ pub struct RingMmap<T> {
 impl<T> Default for RingMmap<T> {
     fn default() -> Self {
         RingMmap {
-            mmap: OwnedMmap(ptr::null_mut(), 0),
+            mmap: Arc::new(OwnedMmap(ptr::null_mut(), 0)),
             producer: ptr::null_mut(),
             consumer: ptr::null_mut(),
             desc: ptr::null_mut(),
@@ -73,7 +189,8 @@ pub struct XdpDesc {
     pub addr: u64,
     /// The length of the packet data.
     pub len: u32,
-    /// Options for the descriptor, currently unused.
+    /// Options for the descriptor: `XDP_PKT_CONTD` marks a multi-buffer packet
+    /// fragment that is continued in the next descriptor.
     pub options: u32,
 }
 
@@ -102,12 +219,6 @@ impl<T> Ring<T>
 where
     T: Copy,
 {
-    /// Returns the size of a single UMEM frame.
-    #[inline]
-    pub fn frame_size(&self) -> u64 {
-        FRAME_SIZE as u64
-    }
-
     /// Memory-maps a ring from a file descriptor.
     ///
     /// # How it works
@@ -186,11 +297,12 @@ impl Ring<u64> {
     /// Fills the ring (typically the Fill Ring) with UMEM frame addresses.
     ///
     /// # Arguments
-    /// * `start_frame` - The starting frame number to begin filling from.
-    pub fn fill(&mut self, start_frame: u32) {
-        for i in 0..self.len as u32 {
-            let desc = self.mut_desc_at(i);
-            *desc = (i + start_frame) as u64 * FRAME_SIZE as u64;
+    /// * `frames` - The base addresses of the frames to put into the ring, at most
+    ///   the ring length.
+    pub fn fill(&mut self, frames: &[u64]) {
+        debug_assert!(frames.len() <= self.len);
+        for (i, &addr) in frames.iter().enumerate() {
+            *self.mut_desc_at(i as u32) = addr;
         }
     }
 }
@@ -198,21 +310,24 @@ impl Ring<u64> {
 impl Ring<XdpDesc> {
     /// Fills the ring (typically the TX ring) with default `XdpDesc` values.
     ///
-    /// This pre-populates the ring with descriptors pointing to corresponding
-    /// UMEM frames.
+    /// This pre-populates the ring with descriptors pointing to the packet data of
+    /// the given UMEM frames.
     ///
     /// # Arguments
-    /// * `start_frame` - The starting frame number to begin filling from.
-    pub fn fill(&mut self, start_frame: u32) {
-        for i in 0..self.len as u32 {
-            let desc = self.mut_desc_at(i);
-            *desc = XdpDesc {
-                addr: (i + start_frame) as u64 * FRAME_SIZE as u64,
+    /// * `layout` - The geometry of the UMEM frames.
+    /// * `frames` - The base addresses of the frames to put into the ring, at most
+    ///   the ring length.
+    pub fn fill(&mut self, layout: &FrameLayout, frames: &[u64]) {
+        debug_assert!(frames.len() <= self.len);
+        for (i, &addr) in frames.iter().enumerate() {
+            *self.mut_desc_at(i as u32) = XdpDesc {
+                addr: layout.data_addr(addr),
                 len: 0,
                 options: 0,
             }
         }
     }
+
     /// Returns a mutable byte slice for a packet buffer in the UMEM.
     ///
     /// This function gets the descriptor at `index`, calculates the memory address
@@ -222,36 +337,70 @@ impl Ring<XdpDesc> {
     /// # Panics
     ///
     /// This function will panic in debug builds if the index or length are out of bounds.
-    pub(crate) fn mut_bytes_at(&mut self, ptr: *mut u8, index: u32, len: usize) -> &mut [u8] {
-        #[cfg(not(feature = "no_safety_checks"))]
-        assert!(index < FRAME_COUNT as u32);
+    #[cfg_attr(feature = "no_safety_checks", allow(unused_variables))]
+    pub(crate) fn mut_bytes_at(
+        &mut self,
+        ptr: *mut u8,
+        layout: &FrameLayout,
+        index: u32,
+        len: usize,
+    ) -> &mut [u8] {
         #[cfg(not(feature = "no_safety_checks"))]
-        assert!((len as u32) < FRAME_SIZE as u32);
+        assert!((index as usize) < self.len);
 
         let desc = self.mut_desc_at(index);
+        let addr = layout.resolve(desc.addr);
 
+        // the buffer must not spill into the next frame
         #[cfg(not(feature = "no_safety_checks"))]
-        assert!(FRAME_SIZE * FRAME_COUNT > desc.addr as usize + len);
+        assert!(len <= layout.room(desc.addr));
+        #[cfg(not(feature = "no_safety_checks"))]
+        assert!(layout.umem_size() >= addr as usize + len);
 
         unsafe {
-            let buf_ptr = ptr.offset(desc.addr as isize);
+            let buf_ptr = ptr.add(addr as usize);
             desc.len = len as u32;
             slice::from_raw_parts_mut(buf_ptr, len)
         }
     }
 
-    /// Sets the descriptor at `index` to a specific length.
+    /// Returns a byte slice of the packet buffer described by the descriptor at
+    /// `index`, with the length of the descriptor.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic in debug builds if the index or length are out of bounds.
+    #[cfg_attr(feature = "no_safety_checks", allow(unused_variables))]
+    pub(crate) fn bytes_at(&self, ptr: *const u8, layout: &FrameLayout, index: u32) -> &[u8] {
+        #[cfg(not(feature = "no_safety_checks"))]
+        assert!((index as usize) < self.len);
+
+        let desc = self.desc_at(index);
+        let addr = layout.resolve(desc.addr);
+
+        #[cfg(not(feature = "no_safety_checks"))]
+        assert!(layout.umem_size() >= addr as usize + desc.len as usize);
+
+        unsafe { slice::from_raw_parts(ptr.add(addr as usize), desc.len as usize) }
+    }
+
+    /// Sets the descriptor at `index` to the packet data of a frame with a specific
+    /// length.
     ///
-    /// The address is calculated based on the index and frame size.
-    pub fn set(&mut self, index: u32, len: u32) {
+    /// # Arguments
+    /// * `layout` - The geometry of the UMEM frames.
+    /// * `index` - The ring index of the descriptor.
+    /// * `addr` - An address within the frame, e.g. one taken from the `FramePool`.
+    /// * `len` - The length of the packet data.
+    pub fn set(&mut self, layout: &FrameLayout, index: u32, addr: u64, len: u32) {
         #[cfg(not(feature = "no_safety_checks"))]
-        assert!(index < FRAME_COUNT as u32);
+        assert!((index as usize) < self.len);
         #[cfg(not(feature = "no_safety_checks"))]
-        assert!(len < FRAME_SIZE as u32);
+        assert!(len as usize <= layout.capacity());
 
         let desc = self.mut_desc_at(index);
         *desc = XdpDesc {
-            addr: (index as u64 * FRAME_SIZE as u64),
+            addr: layout.data_addr(addr),
             len,
             options: 0,
         };
@@ -292,7 +441,7 @@ pub fn mmap_ring<T>(
     let desc = unsafe { map_addr.add(offsets.desc as usize) as *mut T };
     let flags = unsafe { map_addr.add(offsets.flags as usize) as *mut AtomicU32 };
     Ok(RingMmap {
-        mmap: OwnedMmap(map_addr, map_size),
+        mmap: Arc::new(OwnedMmap(map_addr, map_size)),
         producer,
         consumer,
         desc,
@@ -379,3 +528,49 @@ impl RingType {
         Ring::<T>::mmap(raw_fd, ring_size, self.as_offset(), ring_offs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned_layout_maps_addresses_to_frames() {
+        let layout = FrameLayout {
+            frame_size: 2048,
+            frame_count: 16,
+            headroom: 64,
+            tx_metadata_len: 0,
+            unaligned: false,
+        };
+        assert_eq!(layout.frame_addr(3), 3 * 2048 + 64);
+        // a received packet sits after the kernel headroom
+        let rx_addr = 3 * 2048 + 256 + 64;
+        assert_eq!(layout.resolve(rx_addr), rx_addr);
+        assert_eq!(layout.data_addr(rx_addr), layout.frame_addr(3));
+        assert_eq!(layout.fill_addr(rx_addr), rx_addr);
+        assert_eq!(layout.room(rx_addr), 2048 - 256 - 64);
+        assert_eq!(layout.room(layout.frame_base(3)), 2048);
+        assert_eq!(layout.capacity(), 2048 - 64);
+    }
+
+    #[test]
+    fn unaligned_layout_adds_the_offset_bits() {
+        let layout = FrameLayout {
+            frame_size: 3000,
+            frame_count: 16,
+            headroom: 0,
+            tx_metadata_len: 0,
+            unaligned: true,
+        };
+        let base = layout.frame_base(5);
+        let rx_addr = base | (320 << libc::XSK_UNALIGNED_BUF_OFFSET_SHIFT);
+        assert_eq!(layout.resolve(rx_addr), base + 320);
+        assert_eq!(layout.data_addr(rx_addr), base);
+        assert_eq!(layout.fill_addr(rx_addr), base);
+        assert_eq!(layout.room(rx_addr), 3000 - 320);
+        // the offset may point past the frame base given by the low bits
+        let moved = (base + 100) | (200 << libc::XSK_UNALIGNED_BUF_OFFSET_SHIFT);
+        assert_eq!(layout.resolve(moved), base + 300);
+        assert_eq!(layout.fill_addr(moved), base);
+    }
+}
@@ -0,0 +1,173 @@
+//! # Owned Received Frames
+//!
+//! ## Purpose
+//!
+//! This file implements `RxFrame`, an owned handle to a received packet. Unlike the
+//! slice returned by `peek`, which is tied to the ring position of its descriptor and
+//! must be released in order with `commit`, an `RxFrame` can be kept across calls and
+//! handed to worker code, possibly on another thread. Its frame goes back to the UMEM
+//! frame pool when the handle is dropped, in any order.
+//!
+//! ## How it works
+//!
+//! `take_frame` consumes the first available RX descriptor without giving its frame
+//! back to the kernel, and wraps the frame address and length into an `RxFrame`. The
+//! frame then no longer counts as held by the socket. The handle keeps the UMEM alive,
+//! so it stays mapped while the handle exists, and gives the frame back to the
+//! `FramePool` when dropped. The next `seek` or `commit` of the `RxSocket` tops its Fill
+//! ring up from the pool, so the socket keeps the same number of frames in circulation.
+//!
+//! ## Main components
+//!
+//! - `RxFrame`: An owned received packet, returned to the frame pool on drop.
+//! - `impl Socket<_RX>`: Provides `take_frame`.
+
+use crate::socket::{_RX, RingError, Seek_ as _, Socket};
+use crate::umem::Umem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::Arc;
+
+/// A received packet owned by the application.
+///
+/// The frame is returned to the frame pool of its UMEM when the handle is dropped
+/// or released. It dereferences to the packet data.
+pub struct RxFrame {
+    /// The UMEM of the frame, kept alive by the handle.
+    umem: Arc<Umem>,
+    /// A pointer to the packet data in the UMEM.
+    data: *mut u8,
+    /// The UMEM address of the packet, as received.
+    addr: u64,
+    /// The length of the packet data.
+    len: u32,
+    /// The options of the received descriptor.
+    options: u32,
+}
+
+// the frame is owned exclusively by the handle until it is dropped, and its
+//  UMEM mapping is kept alive by umem
+unsafe impl Send for RxFrame {}
+unsafe impl Sync for RxFrame {}
+
+impl RxFrame {
+    /// Returns the UMEM address of the packet, as received.
+    #[inline]
+    pub fn addr(&self) -> u64 {
+        self.addr
+    }
+
+    /// Returns the length of the packet data.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if the packet has no data.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the packet continues in the next received frame, that is
+    /// `XDP_PKT_CONTD` is set on a fragment of a multi-buffer packet.
+    #[inline]
+    pub fn is_continued(&self) -> bool {
+        self.options & libc::XDP_PKT_CONTD != 0
+    }
+
+    /// Returns the frame to the frame pool, like dropping the handle.
+    #[inline]
+    pub fn release(self) {}
+}
+
+impl Deref for RxFrame {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data, self.len as usize) }
+    }
+}
+
+impl DerefMut for RxFrame {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.data, self.len as usize) }
+    }
+}
+
+impl AsRef<[u8]> for RxFrame {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for RxFrame {
+    fn drop(&mut self) {
+        self.umem.pool().free(self.addr);
+    }
+}
+
+impl Socket<_RX> {
+    /// Takes the first received packet out of the RX ring as an owned `RxFrame`,
+    /// seeking for one if none is available.
+    ///
+    /// The descriptor is consumed, so the remaining available descriptors shift by
+    /// one. Its frame stays with the handle until it is dropped or released, and then
+    /// goes back to the frame pool. The next `seek` or `commit` of this socket
+    /// replaces it in the Fill ring with a frame from the pool; while the pool is
+    /// empty, the socket receives into fewer frames.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `RxFrame` of the first received packet.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::RingEmpty` if no packet has been received.
+    pub fn take_frame(&mut self) -> Result<RxFrame, RingError> {
+        self.seek_(1)?;
+        let umem = self
+            ._inner
+            .as_ref()
+            .ok_or(RingError::NotAvailable)?
+            .umem()
+            .clone();
+        let desc = self.x_ring.desc_at(self.consumer & self.x_ring.mod_mask);
+        let data = unsafe { self.frames.add(self.layout.resolve(desc.addr) as usize) };
+        self.consumer = self.consumer.wrapping_add(1);
+        self.available -= 1;
+        self.owned.remove(desc.addr);
+        self.x_ring.update_consumer(self.consumer);
+        Ok(RxFrame {
+            umem,
+            data,
+            addr: desc.addr,
+            len: desc.len,
+            options: desc.options,
+        })
+    }
+
+    /// Tops the Fill ring up with frames from the pool, replacing the frames taken
+    /// out with `take_frame`.
+    pub(crate) fn refill(&mut self) {
+        if self.owned.len() >= self.frame_quota {
+            return;
+        }
+        let Some(inner) = &self._inner else {
+            return;
+        };
+        let f_ring = &mut self.u_ring;
+        let producer = &mut self.producer;
+        let owned = &mut self.owned;
+        let missing = (self.frame_quota - owned.len()) as usize;
+        // pool frames are frame base addresses, as the Fill ring expects them
+        let count = inner.umem().pool().alloc_with(missing, |addr| {
+            *f_ring.mut_desc_at(*producer & f_ring.mod_mask) = addr;
+            *producer = producer.wrapping_add(1);
+            owned.insert(addr);
+        });
+        if count > 0 {
+            f_ring.update_producer(*producer);
+        }
+    }
+}
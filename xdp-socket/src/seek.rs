@@ -16,13 +16,22 @@
 //! ring for sending packets. If the ring is low on free descriptors, it checks the
 //! Completion Ring for packets that the kernel has finished sending. It reclaims
 //! these completed descriptors, making their associated UMEM frames available for new
-//! transmissions, and updates the count of available TX slots.
+//! transmissions, and updates the count of available TX slots. The completion
+//! timestamps of reclaimed frames are dropped and their cookies are queued for
+//! `completed_cookies`; `completions` reclaims frames the same way and yields them.
 //!
 //! For `_RX`, the `seek_` method checks for newly received packets in the RX ring
 //! that are ready to be read by the application. It updates its internal count of
 //! available packets by checking the ring's producer index, which is advanced by the
 //! kernel when packets are received.
 //!
+//! Frames taken out with `take_frame` are first replaced in the Fill ring with frames
+//! from the UMEM frame pool.
+//!
+//! When a busy-polling socket finds nothing new, either direction first makes a
+//! non-blocking wakeup syscall, which runs the driver from the calling thread, and
+//! checks the ring again.
+//!
 //! ## Main components
 //!
 //! - `Seek_` trait: Defines the internal `seek_` interface.
@@ -31,7 +40,7 @@
 //! - `impl Seek_<_RX> for Socket<_RX>`: The implementation of the seek logic for the
 //!   receive socket.
 
-use crate::ring::XdpDesc;
+use crate::kick::wake_up;
 use crate::socket::{_RX, _TX, RingError, Seek_, Socket};
 
 /// Implements the seeking logic for a transmit (`TX`) socket.
@@ -51,20 +60,17 @@ impl Seek_<_TX> for Socket<_TX> {
         if self.available as usize >= count {
             return Ok(count);
         }
-        let c_ring = &mut self.u_ring;
-        let c_producer = c_ring.producer();
+        let mut c_producer = self.u_ring.producer();
+        if c_producer == self.consumer && self.busy_poll {
+            // busy polling completes transmitted frames from this thread
+            wake_up(self.raw_fd, _TX).map_err(RingError::Io)?;
+            c_producer = self.u_ring.producer();
+        }
         if c_producer == self.consumer {
             Err(RingError::RingFull)
         } else {
             loop {
-                let c_head = self.consumer & c_ring.mod_mask;
-                let addr = c_ring.desc_at(c_head);
-                let desc = XdpDesc::new(addr, 0, 0);
-                self.consumer = self.consumer.wrapping_add(1);
-                c_ring.update_consumer(self.consumer);
-                let x_head = self.producer & self.x_ring.mod_mask;
-                *self.x_ring.mut_desc_at(x_head) = desc;
-                self.available += 1;
+                self.reclaim_deferred();
                 if self.available as usize >= count || c_producer == self.consumer {
                     break;
                 }
@@ -87,10 +93,16 @@ impl Seek_<_RX> for Socket<_RX> {
     /// A `Result` containing the number of descriptors successfully sought, or a
     /// `RingError` if the operation fails.
     fn seek_(&mut self, count: usize) -> Result<usize, RingError> {
+        self.refill();
         if self.available as usize >= count {
             return Ok(count);
         }
-        let x_producer = self.x_ring.producer();
+        let mut x_producer = self.x_ring.producer();
+        if x_producer == self.consumer && self.busy_poll {
+            // busy polling receives packets from this thread
+            wake_up(self.raw_fd, _RX).map_err(RingError::Io)?;
+            x_producer = self.x_ring.producer();
+        }
         if x_producer == self.consumer {
             Err(RingError::RingEmpty)
         } else {
@@ -11,21 +11,55 @@
 //!
 //! ## How it works
 //!
-//! The user must first call `seek` or `seek_n` to ensure one or more UMEM frames
-//! are available for writing. The `send` method then takes the user's packet data,
-//! copies it into the next available frame, and calls `commit` to submit the
-//! descriptor to the kernel for transmission. It also provides a `send_blocking`
-//! variant that waits for the send to complete.
+//! The `send` methods make the frames of the packet available themselves: when too
+//! few free frames are left, they reclaim completed frames from the Completion ring
+//! like `seek`. If the ring is still full, the `Backpressure` policy of the socket
+//! decides whether to report `RingError::RingFull` right away, so the caller can
+//! try again later, or to kick the kernel and spin or poll until frames are
+//! completed. The `send` method then copies the user's packet data into the next
+//! available frame, and calls `commit` to submit the descriptor to the kernel for
+//! transmission. It also provides a `send_blocking`
+//! variant that waits until the kernel returns the frame on the Completion ring, so
+//! the packet is known to have left the socket, and `send_blocking_timeout` to bound
+//! that wait.
 //!
 //! ## Main components
 //!
-//! - `impl Socket<_TX>`: An implementation block for the transmit socket.
+//! - `Backpressure`: What `send` does when no frame is free.
+//! - `impl Socket<_TX>`: An implementation block for the transmit socket, with
+//!   `set_backpressure`.
 //! - `send()`: A non-blocking method to send a slice of data.
-//! - `send_blocking()`: A blocking method that sends data and waits for the operation
-//!   to be acknowledged by the kernel.
+//! - `send_vectored()`: A non-blocking method to send a packet gathered from several
+//!   slices.
+//! - `send_blocking()`: A blocking method that sends data and waits for the frame to be
+//!   completed by the kernel.
+//! - `send_blocking_timeout()`: Like `send_blocking`, with a timeout.
 
+use crate::completion::TxCompletion;
 use crate::poll::PollWaitExt;
 use crate::socket::{_TX, Commit_, RingError, Seek_, Socket};
+use std::hint;
+use std::io::{self, IoSlice};
+use std::time::{Duration, Instant};
+
+/// What the `send` methods do when the TX ring has no free frame left after
+/// reclaiming the completed ones.
+///
+/// Set it with `XdpConfig::backpressure` or `Socket::set_backpressure`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Returns `RingError::RingFull` right away, after kicking the kernel if it
+    /// needs a wakeup to make progress. The send can be retried later.
+    #[default]
+    Fail,
+    /// Kicks the kernel and spins until frames are completed, for at most the given
+    /// time, then returns `RingError::RingFull`. `None` spins indefinitely.
+    Spin(Option<Duration>),
+    /// Kicks the kernel and waits with `poll_wait` until frames are completed, for at
+    /// most the given time, then returns `RingError::RingFull`. `None` waits
+    /// indefinitely.
+    Wait(Option<Duration>),
+}
 
 /// A trait for high-level packet sending operations on XDP transmit sockets.
 ///
@@ -33,9 +67,11 @@ use crate::socket::{_TX, Commit_, RingError, Seek_, Socket};
 /// away the details of descriptor management and UMEM frame handling, offering a simple
 /// interface for non-blocking and blocking sends.
 ///
-/// - `send`: Sends a packet in a non-blocking manner. You must ensure a frame is available
-///   by calling `seek` or `seek_n` before use.
-/// - `send_blocking`: Sends a packet and blocks until the kernel has processed the send.
+/// - `send`: Sends a packet, reclaiming completed frames when none is free and applying
+///   the `Backpressure` policy of the socket when the ring is full.
+/// - `send_vectored`: Like `send`, with the packet gathered from several slices.
+/// - `send_blocking`: Sends a packet and blocks until the kernel has completed its frame.
+/// - `send_blocking_timeout`: Like `send_blocking`, giving up after a timeout.
 ///
 /// # Arguments
 ///
@@ -44,7 +80,9 @@ use crate::socket::{_TX, Commit_, RingError, Seek_, Socket};
 ///
 /// # Errors
 ///
-/// Returns a `RingError` if the send fails or if no frame is available.
+/// Returns `RingError::RingFull` if no frame is free, which only means the send can be
+/// retried later (see `RingError::is_retryable`), and another `RingError` if the send
+/// fails.
 ///
 /// # Example
 ///
@@ -55,7 +93,14 @@ use crate::socket::{_TX, Commit_, RingError, Seek_, Socket};
 /// ```
 pub trait SendExt {
     fn send(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError>;
+    fn send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<(), RingError>;
     fn send_blocking(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError>;
+    fn send_blocking_timeout(
+        &mut self,
+        data: &[u8],
+        header: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<TxCompletion, RingError>;
 }
 
 /// An implementation block for the transmit socket (`TxSocket`) that provides
@@ -64,14 +109,16 @@ impl SendExt for Socket<_TX>
 where
     Socket<_TX>: Seek_<_TX> + Commit_<_TX> + PollWaitExt<_TX>,
 {
-    /// Sends a packet in a non-blocking manner.
+    /// Sends a packet.
     ///
-    /// This method copies the provided data into a UMEM frame that has been
-    /// previously acquired via a call to `seek` or `seek_n`, and then submits it
-    /// to the kernel for transmission.
+    /// This method copies the provided data into a free UMEM frame and then submits
+    /// it to the kernel for transmission. On a multi-buffer socket, a packet larger
+    /// than a frame is split over a chain of frames with `write_packet`.
     ///
-    /// Before calling this function, you must ensure that a frame is available by
-    /// calling `seek` or `seek_n`.
+    /// Frames made available with `seek` or `seek_n` are used first. When there are
+    /// not enough of them, completed frames are reclaimed from the Completion ring,
+    /// and if the ring is still full the `Backpressure` policy of the socket applies;
+    /// with the default policy, the call does not block.
     ///
     /// # Arguments
     /// * `data` - A byte slice containing the packet payload.
@@ -83,27 +130,57 @@ where
     ///
     /// # Errors
     ///
-    /// Returns `RingError::InvalidLength` if `data.len() + header.len()` exceeds
-    /// the UMEM frame size. Returns `RingError::InvalidIndex` if `seek` has not
-    /// been called to make a frame available.
+    /// Returns `RingError::RingFull` if no frame became free under the backpressure
+    /// policy; nothing is sent and the call can be retried. Returns
+    /// `RingError::InvalidLength` if `data.len() + header.len()` exceeds the UMEM
+    /// frame size on a socket without multi-buffer support, or the frames of the
+    /// socket on one with it. Returns `RingError::Io` if a wakeup syscall or `poll`
+    /// fails.
     fn send(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError> {
-        let hdr_len = header.map_or(0, |h| h.len());
-        let buf_len = data.len() + hdr_len;
-        let buf = self.peek(buf_len)?;
-        if let Some(bs) = header {
-            buf[0..hdr_len].copy_from_slice(bs);
-        }
-        buf[hdr_len..].copy_from_slice(data);
-        self.commit()
+        self.reserve(data.len() + header.map_or(0, <[u8]>::len))?;
+        let count = self.write_packet(0, data, header)?;
+        self.commit_(count)
     }
 
-    /// Sends a packet and blocks until the kernel has processed the send.
+    /// Sends a packet gathered from several slices in a non-blocking manner.
     ///
-    /// This method first calls `send` to queue the packet and then blocks, waiting
-    /// for a kernel notification that the send is complete.
+    /// The slices, e.g. the Ethernet, IP and UDP headers, an application header and
+    /// payload chunks, are copied one after another into a UMEM frame with
+    /// `write_packet_vectored`, so no temporary buffer is needed to assemble the
+    /// packet. Free frames are found as with `send`.
     ///
-    /// Before calling this function, you must ensure that a frame is available by
-    /// calling `seek` or `seek_n`.
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// tx.send_vectored(&[
+    ///     IoSlice::new(&headers),
+    ///     IoSlice::new(&app_header),
+    ///     IoSlice::new(payload),
+    /// ])?;
+    /// ```
+    ///
+    /// # Arguments
+    /// * `bufs` - The slices making up the packet, in order.
+    ///
+    /// # Returns
+    /// A `Result` indicating success or a `RingError` on failure.
+    ///
+    /// # Errors
+    ///
+    /// The same as `send`.
+    fn send_vectored(&mut self, bufs: &[IoSlice<'_>]) -> Result<(), RingError> {
+        self.reserve(bufs.iter().map(|buf| buf.len()).sum())?;
+        let count = self.write_packet_vectored(0, bufs)?;
+        self.commit_(count)
+    }
+
+    /// Sends a packet and blocks until the kernel has completed its frame.
+    ///
+    /// This method queues the packet like `send` and then waits with
+    /// `wait_completion` until its frame comes back on the Completion ring, so the
+    /// packet has left the socket when it returns. Frames completed meanwhile are
+    /// reclaimed and become available for sending. Free frames are found as with
+    /// `send`.
     ///
     /// # Arguments
     /// * `data` - A byte slice containing the packet payload.
@@ -117,8 +194,188 @@ where
     /// In addition to the errors from `send`, this function can return
     /// `RingError::Io` if the underlying `poll_wait` fails.
     fn send_blocking(&mut self, data: &[u8], header: Option<&[u8]>) -> Result<(), RingError> {
-        self.send(data, header)?;
-        self.poll_wait(None).map_err(RingError::Io)?;
-        Ok(())
+        self.send_and_wait(data, header, None).map(|_| ())
+    }
+
+    /// Sends a packet and blocks until the kernel has completed its frame or the
+    /// timeout expires.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let completion = tx.send_blocking_timeout(&packet, None, Duration::from_millis(5))?;
+    /// // the packet has left the socket, the resources it refers to can be reused
+    /// ```
+    ///
+    /// # Arguments
+    /// * `data` - A byte slice containing the packet payload.
+    /// * `header` - An optional byte slice for the packet header.
+    /// * `timeout` - The maximum time to wait for the completion.
+    ///
+    /// # Returns
+    /// A `Result` containing the `TxCompletion` of the packet, with its TX timestamp
+    /// if one was requested.
+    ///
+    /// # Errors
+    ///
+    /// In addition to the errors from `send`, returns `RingError::Io` with an error of
+    /// kind `TimedOut` if the frame is not completed in time. The packet stays queued
+    /// in that case.
+    fn send_blocking_timeout(
+        &mut self,
+        data: &[u8],
+        header: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<TxCompletion, RingError> {
+        self.send_and_wait(data, header, Some(timeout))
+    }
+}
+
+impl Socket<_TX> {
+    /// Returns the backpressure policy of the `send` methods.
+    #[inline]
+    pub fn backpressure(&self) -> Backpressure {
+        self.backpressure
+    }
+
+    /// Sets what the `send` methods do when no frame is free, see `Backpressure`.
+    ///
+    /// # Arguments
+    /// * `backpressure` - The new policy.
+    #[inline]
+    pub fn set_backpressure(&mut self, backpressure: Backpressure) {
+        self.backpressure = backpressure;
+    }
+
+    /// Returns the number of frames a packet of `len` bytes occupies: one, or as many
+    /// as its chain needs on a multi-buffer socket.
+    #[inline]
+    pub(crate) fn frames_for(&self, len: usize) -> usize {
+        if self.multi_buffer {
+            len.div_ceil(self.layout.capacity().max(1)).max(1)
+        } else {
+            1
+        }
+    }
+
+    /// Makes enough frames available for a packet of `len` bytes, reclaiming
+    /// completed frames and applying the backpressure policy.
+    pub(crate) fn reserve(&mut self, len: usize) -> Result<(), RingError> {
+        // a packet that never fits must not wait for a frame first
+        if !self.multi_buffer && len > self.layout.capacity() {
+            return Err(RingError::InvalidLength);
+        }
+        let count = self.frames_for(len);
+        if self.available as usize >= count {
+            return Ok(());
+        }
+        // more frames than the socket owns never become free
+        if count > self.frame_quota as usize {
+            return Err(RingError::InvalidLength);
+        }
+        let deadline = match self.backpressure {
+            Backpressure::Fail => None,
+            Backpressure::Spin(timeout) | Backpressure::Wait(timeout) => {
+                timeout.map(|timeout| Instant::now() + timeout)
+            }
+        };
+        loop {
+            match self.seek_(count) {
+                Ok(_) | Err(RingError::RingFull) => {}
+                Err(e) => return Err(e),
+            }
+            if self.available as usize >= count {
+                return Ok(());
+            }
+            let left = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if self.backpressure == Backpressure::Fail || left.is_some_and(|left| left.is_zero()) {
+                self.kick().map_err(RingError::Io)?;
+                return Err(RingError::RingFull);
+            }
+            if let Backpressure::Wait(_) = self.backpressure {
+                match self.poll_wait(left) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                    Err(e) => return Err(RingError::Io(e)),
+                }
+            } else {
+                self.kick().map_err(RingError::Io)?;
+                hint::spin_loop();
+            }
+        }
+    }
+
+    /// Sends a packet and waits for the completion of its last frame.
+    fn send_and_wait(
+        &mut self,
+        data: &[u8],
+        header: Option<&[u8]>,
+        timeout: Option<Duration>,
+    ) -> Result<TxCompletion, RingError> {
+        self.reserve(data.len() + header.map_or(0, <[u8]>::len))?;
+        let count = self.write_packet(0, data, header)?;
+        // a chain completes in order, its last frame comes back last
+        let addr = self.addr_at(count - 1)?;
+        self.commit_(count)?;
+        self.wait_completion(addr, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ring::FrameLayout;
+
+    fn socket(multi_buffer: bool) -> Socket<_TX> {
+        let mut socket = Socket::<_TX>::default();
+        socket.layout = FrameLayout {
+            frame_size: 2048,
+            frame_count: 64,
+            headroom: 64,
+            tx_metadata_len: 0,
+            unaligned: false,
+        };
+        socket.multi_buffer = multi_buffer;
+        socket.frame_quota = 4;
+        socket
+    }
+
+    #[test]
+    fn packets_take_one_frame_without_multi_buffer() {
+        let socket = socket(false);
+        assert_eq!(socket.frames_for(0), 1);
+        assert_eq!(socket.frames_for(1984), 1);
+        assert_eq!(socket.frames_for(9000), 1);
+    }
+
+    #[test]
+    fn multi_buffer_packets_take_a_frame_per_capacity() {
+        let socket = socket(true);
+        assert_eq!(socket.frames_for(0), 1);
+        assert_eq!(socket.frames_for(1984), 1);
+        assert_eq!(socket.frames_for(1985), 2);
+        assert_eq!(socket.frames_for(9000), 5);
+    }
+
+    #[test]
+    fn reserve_counts_the_frames_of_a_packet() {
+        let mut socket = socket(true);
+        socket.available = 2;
+        assert!(socket.reserve(3968).is_ok());
+        // more frames than the socket keeps in circulation
+        assert!(matches!(
+            socket.reserve(9000),
+            Err(RingError::InvalidLength)
+        ));
+
+        // an oversized packet fails before any frame is looked for
+        let mut socket = self::socket(false);
+        socket.set_backpressure(Backpressure::Wait(None));
+        assert!(matches!(
+            socket.reserve(1985),
+            Err(RingError::InvalidLength)
+        ));
+        socket.available = 1;
+        assert!(socket.reserve(1984).is_ok());
     }
 }
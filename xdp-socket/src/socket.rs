@@ -26,7 +26,7 @@
 //! - `Ring<T>`: A generic ring buffer implementation that is used for the TX/RX rings and
 //!   the Fill/Completion rings for UMEM.
 //! - `Inner`: A struct that holds the owned file descriptor for the XDP socket and the
-//!   memory-mapped UMEM region.
+//!   possibly shared UMEM region.
 //! - `TxSocket` and `RxSocket`: Type aliases for `Socket<true>` and `Socket<false>`
 //!   respectively, providing a more intuitive API for users.
 
@@ -34,11 +34,14 @@
 #![allow(private_bounds)]
 #![allow(non_upper_case_globals)]
 
-use crate::mmap::OwnedMmap;
-use crate::ring::{Ring, XdpDesc};
+use crate::cookie::TxCookies;
+use crate::frame_pool::FrameSet;
+use crate::ring::{FrameLayout, Ring, XdpDesc};
+use crate::send::Backpressure;
+use crate::umem::Umem;
 use std::fmt::Display;
-use std::os::fd::{AsRawFd as _, OwnedFd};
-use std::sync::Arc;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::sync::{Arc, Mutex};
 use std::{io, ptr};
 
 /// A high-level interface for an AF_XDP socket.
@@ -46,22 +49,38 @@ use std::{io, ptr};
 /// This struct is generic over the `_Direction` const parameter, which determines
 /// whether the socket is for sending (`_TX`) or receiving (`_RX`).
 pub struct Socket<const t: _Direction> {
-    /// The inner shared state, including the file descriptor and UMEM.
-    pub(crate) _inner: Option<Arc<Inner>>,
     /// The primary ring for sending (TX) or receiving (RX) descriptors.
     pub(crate) x_ring: Ring<XdpDesc>,
     /// The UMEM-associated ring: Completion Ring for TX, Fill Ring for RX.
     pub(crate) u_ring: Ring<u64>,
     /// The number of available descriptors in the `x_ring`.
     pub(crate) available: u32,
-    /// The cached producer index for the `x_ring`.
+    /// The cached producer index: of the TX ring for TX, of the Fill ring for RX.
     pub(crate) producer: u32,
-    /// The cached consumer index for the `x_ring`.
+    /// The cached consumer index: of the Completion ring for TX, of the RX ring for RX.
     pub(crate) consumer: u32,
     /// A raw pointer to the start of the UMEM frames area.
     pub(crate) frames: *mut u8,
+    /// The geometry of the UMEM frames.
+    pub(crate) layout: FrameLayout,
+    /// Whether the socket is bound with `XDP_USE_SG` for multi-buffer packets.
+    pub(crate) multi_buffer: bool,
+    /// Whether the socket busy polls, so every `kick` and empty `seek` makes a syscall.
+    pub(crate) busy_poll: bool,
+    /// The number of UMEM frames the socket keeps in circulation.
+    pub(crate) frame_quota: u32,
+    /// The UMEM frames the socket currently holds; for RX, frames taken out with
+    /// `take_frame` are not included.
+    pub(crate) owned: FrameSet,
+    /// The cookies attached to TX frames until they are completed; unused for RX.
+    pub(crate) cookies: TxCookies,
+    /// What the `send` methods do when no TX frame is free; unused for RX.
+    pub(crate) backpressure: Backpressure,
     /// -
     pub(crate) raw_fd: libc::c_int,
+    /// The inner shared state, including the file descriptor and UMEM. Dropped after
+    /// the rings, so the kernel socket is released when the last one is unmapped.
+    pub(crate) _inner: Option<Arc<Inner>>,
 }
 
 /// An error that can occur during ring operations.
@@ -77,6 +96,10 @@ pub enum RingError {
     InvalidIndex,
     /// The provided data length exceeds the available space in a UMEM frame.
     InvalidLength,
+    /// The sockets involved in the operation do not share the same UMEM.
+    UmemMismatch,
+    /// No TX metadata area is configured for the UMEM.
+    NoMetadata,
     /// An underlying I/O error occurred.
     Io(io::Error),
 }
@@ -89,11 +112,22 @@ impl Display for RingError {
             RingError::NotAvailable => write!(f, "Not enough available frames"),
             RingError::InvalidIndex => write!(f, "Invalid index for ring access"),
             RingError::InvalidLength => write!(f, "Invalid length for ring access"),
+            RingError::UmemMismatch => write!(f, "Sockets do not share the same UMEM"),
+            RingError::NoMetadata => write!(f, "No TX metadata area is configured"),
             RingError::Io(e) => write!(f, "I/O error: {e}"),
         }
     }
 }
 
+impl RingError {
+    /// Returns `true` if the error only reports a full or empty ring, so the
+    /// operation can be retried once the kernel has made progress.
+    #[inline]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, RingError::RingFull | RingError::RingEmpty)
+    }
+}
+
 impl<const t: _Direction> Socket<t>
 where
     Socket<t>: Seek_<t> + Commit_<t> + Send,
@@ -101,24 +135,43 @@ where
     /// Constructs a new `Socket`.
     ///
     /// This function initializes a socket for either sending or receiving based on the
-    /// generic const `t`. For TX sockets, it pre-fills the TX ring with descriptors
-    /// pointing to UMEM frames. For RX sockets, it pre-fills the Fill ring to provide
-    /// the kernel with available frames for incoming packets.
+    /// generic const `t`. The rings are expected to be pre-filled by the caller: for TX
+    /// sockets, the TX ring holds descriptors pointing to the `frames`. For RX sockets,
+    /// the Fill ring has already provided the kernel with the `frames` for incoming
+    /// packets.
     ///
     /// # Arguments
     ///
     /// * `inner` - The shared inner socket state (file descriptor, UMEM).
     /// * `x_ring` - The TX or RX ring.
     /// * `u_ring` - The Completion or Fill ring.
-    /// * `skip_frames` - The number of frames to skip at the start of the UMEM.
-    pub(crate) fn new(inner: Option<Arc<Inner>>, x_ring: Ring<XdpDesc>, u_ring: Ring<u64>) -> Self {
+    /// * `frames` - The base addresses of the UMEM frames owned by this socket.
+    pub(crate) fn new(
+        inner: Option<Arc<Inner>>,
+        x_ring: Ring<XdpDesc>,
+        u_ring: Ring<u64>,
+        frames: &[u64],
+    ) -> Self {
         if let Some(inner) = inner {
-            let frames = inner.umem.0 as *mut u8;
-            let raw_fd = inner.fd.as_raw_fd();
+            let layout = inner.umem.layout();
+            let owned = FrameSet::new(layout, frames);
+            let frames = frames.len() as u32;
+            let (available, producer) = match t {
+                _TX => (frames, 0), // free frames are parked in the TX ring
+                _RX => (0, frames), // all frames are already in the Fill ring
+            };
+            let raw_fd = inner.raw_fd();
             Self {
-                frames,
-                available: x_ring.len as u32,
-                producer: 0,
+                frames: inner.umem.frames(),
+                layout,
+                multi_buffer: inner.umem.multi_buffer(),
+                busy_poll: inner.busy_poll,
+                frame_quota: frames,
+                owned,
+                cookies: TxCookies::default(),
+                backpressure: Backpressure::default(),
+                available,
+                producer,
                 consumer: 0,
                 raw_fd,
                 _inner: Some(inner),
@@ -205,7 +258,38 @@ where
     /// The size of a single frame in the UMEM in bytes.
     #[inline]
     pub fn frame_size(&self) -> usize {
-        self.x_ring.frame_size() as usize
+        self.layout.frame_size as usize
+    }
+
+    /// Returns the number of bytes available for packet data in a single frame,
+    /// that is the frame size minus the configured headroom and TX metadata area.
+    #[inline]
+    pub fn frame_capacity(&self) -> usize {
+        self.layout.capacity()
+    }
+
+    /// Returns `true` if the socket is bound with `XDP_USE_SG`, so packets larger
+    /// than a frame span a chain of descriptors.
+    #[inline]
+    pub fn is_multi_buffer(&self) -> bool {
+        self.multi_buffer
+    }
+
+    /// Returns `true` if this socket and `other` use the same UMEM, so frame
+    /// addresses of one socket are valid for the other.
+    pub fn shares_umem_with<const u: _Direction>(&self, other: &Socket<u>) -> bool {
+        match (&self._inner, &other._inner) {
+            (Some(a), Some(b)) => Arc::ptr_eq(&a.umem, &b.umem),
+            _ => false,
+        }
+    }
+}
+
+impl<const t: _Direction> AsRawFd for Socket<t> {
+    /// Returns the raw file descriptor of the AF_XDP socket, e.g. to register it
+    /// with an event loop.
+    fn as_raw_fd(&self) -> RawFd {
+        self.raw_fd
     }
 }
 
@@ -239,6 +323,13 @@ impl<const t: _Direction> Default for Socket<t> {
             producer: 0,
             consumer: 0,
             frames: ptr::null_mut(),
+            layout: FrameLayout::default(),
+            multi_buffer: false,
+            busy_poll: false,
+            frame_quota: 0,
+            owned: FrameSet::default(),
+            cookies: TxCookies::default(),
+            backpressure: Backpressure::default(),
             raw_fd: 0,
         }
     }
@@ -255,15 +346,81 @@ pub(crate) trait Commit_<const t: _Direction> {
 
 /// Holds the owned components of an XDP socket that can be shared.
 pub(crate) struct Inner {
-    /// The memory-mapped UMEM region.
-    umem: OwnedMmap,
-    /// The owned file descriptor for the AF_XDP socket.
-    fd: OwnedFd,
+    /// The UMEM region, possibly shared with other sockets.
+    umem: Arc<Umem>,
+    /// The owned file descriptor for the AF_XDP socket, or `None` if the socket
+    /// is the one the UMEM is registered on.
+    fd: Option<OwnedFd>,
+    /// Whether busy polling is enabled on the AF_XDP socket.
+    pub(crate) busy_poll: bool,
+    /// The frames of the dropped socket halves, returned to the pool once the AF_XDP
+    /// socket is closed.
+    released: Mutex<Vec<u64>>,
 }
 
 impl Inner {
     /// Constructs a new `Inner` with the given UMEM and file descriptor.
-    pub(crate) fn new(umem: OwnedMmap, fd: OwnedFd) -> Self {
-        Self { umem, fd }
+    pub(crate) fn new(umem: Arc<Umem>, fd: Option<OwnedFd>, busy_poll: bool) -> Self {
+        Self {
+            umem,
+            fd,
+            busy_poll,
+            released: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records the frames of a dropped socket half, to be returned to the pool when
+    /// the AF_XDP socket is closed.
+    fn release(&self, frames: Vec<u64>) {
+        self.released
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend(frames);
+    }
+
+    /// Returns the UMEM of the socket.
+    #[inline]
+    pub(crate) fn umem(&self) -> &Arc<Umem> {
+        &self.umem
+    }
+
+    /// Returns the raw file descriptor of the AF_XDP socket.
+    pub(crate) fn raw_fd(&self) -> libc::c_int {
+        self.fd
+            .as_ref()
+            .map_or_else(|| self.umem.raw_fd(), |fd| fd.as_raw_fd())
+    }
+}
+
+impl<const t: _Direction> Drop for Socket<t> {
+    /// Hands the frames of the socket over to be returned to the UMEM frame pool.
+    ///
+    /// The frames go back to the pool when the AF_XDP socket is closed, after both
+    /// halves of a bidirectional socket are dropped. Sockets should be removed from an
+    /// `XdpPoller` before, as its entries keep the rings, and the kernel socket, alive.
+    fn drop(&mut self) {
+        if let Some(inner) = &self._inner {
+            inner.release(self.owned.take_all());
+        }
+    }
+}
+
+impl Drop for Inner {
+    /// Closes the AF_XDP socket and returns the frames of its halves to the pool.
+    fn drop(&mut self) {
+        if self.umem.is_private() {
+            // the UMEM goes away with this socket, even if frames outlive it
+            return;
+        }
+        // the kernel stops using the frames once the socket is released
+        if self.fd.take().is_none() {
+            // the registering socket stays open with the UMEM, it is replaced
+            if let Err(e) = self.umem.reset() {
+                log::error!("Failed to release the UMEM socket, its frames are lost: {e}");
+                return;
+            }
+        }
+        let released = self.released.get_mut().unwrap_or_else(|e| e.into_inner());
+        self.umem.release(released);
     }
 }
@@ -0,0 +1,94 @@
+//! # XDP Socket Statistics
+//!
+//! ## Purpose
+//!
+//! This file implements the `statistics` method for the `Socket`, which reports the
+//! drop and error counters the kernel keeps for an AF_XDP socket. They make it possible
+//! to monitor packet loss, e.g. to alert when the Fill ring runs empty.
+//!
+//! ## How it works
+//!
+//! The counters are read with `getsockopt(SOL_XDP, XDP_STATISTICS)`. Kernels older than
+//! 5.9 only know the first three counters and return the shorter `xdp_statistics_v1`
+//! struct; the returned option length tells which one was filled, and the counters
+//! missing from the short struct are reported as `None`.
+//!
+//! The counters belong to the AF_XDP socket, so both halves of a bidirectional socket
+//! report the same values.
+//!
+//! ## Main components
+//!
+//! - `XdpStatistics`: The counters of a socket.
+//! - `statistics()`: Reads the counters from the kernel.
+
+#![allow(non_upper_case_globals)]
+
+use crate::socket::{_Direction, Socket};
+use std::io;
+use std::mem::size_of;
+
+/// The drop and error counters of an AF_XDP socket.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct XdpStatistics {
+    /// Packets dropped for reasons other than invalid descriptors, e.g. a full RX ring.
+    pub rx_dropped: u64,
+    /// Packets dropped due to invalid RX descriptors.
+    pub rx_invalid_descs: u64,
+    /// Packets dropped due to invalid TX descriptors.
+    pub tx_invalid_descs: u64,
+    /// Packets dropped because the RX ring was full. `None` on kernels before 5.9.
+    pub rx_ring_full: Option<u64>,
+    /// Times the Fill ring was found empty while a packet had to be received.
+    /// `None` on kernels before 5.9.
+    pub rx_fill_ring_empty_descs: Option<u64>,
+    /// Times the TX ring was found empty while the kernel tried to transmit.
+    /// `None` on kernels before 5.9.
+    pub tx_ring_empty_descs: Option<u64>,
+}
+
+impl<const t: _Direction> Socket<t> {
+    /// Reads the drop and error counters of the socket from the kernel.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `XdpStatistics` of the socket. The counters
+    /// introduced in Linux 5.9 are `None` on older kernels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `io::Error` if `getsockopt` fails, or if the kernel returns a struct
+    /// of unexpected size.
+    pub fn statistics(&self) -> io::Result<XdpStatistics> {
+        let mut stats: libc::xdp_statistics = unsafe { std::mem::zeroed() };
+        let mut optlen = size_of::<libc::xdp_statistics>() as libc::socklen_t;
+        unsafe {
+            if libc::getsockopt(
+                self.raw_fd,
+                libc::SOL_XDP,
+                libc::XDP_STATISTICS,
+                &mut stats as *mut _ as *mut libc::c_void,
+                &mut optlen,
+            ) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let extended = match optlen as usize {
+            len if len == size_of::<libc::xdp_statistics>() => true,
+            len if len == size_of::<libc::xdp_statistics_v1>() => false,
+            len => {
+                return Err(io::Error::other(format!(
+                    "unexpected XDP_STATISTICS size {len}"
+                )));
+            }
+        };
+        Ok(XdpStatistics {
+            rx_dropped: stats.rx_dropped,
+            rx_invalid_descs: stats.rx_invalid_descs,
+            tx_invalid_descs: stats.tx_invalid_descs,
+            rx_ring_full: extended.then_some(stats.rx_ring_full),
+            rx_fill_ring_empty_descs: extended.then_some(stats.rx_fill_ring_empty_descs),
+            tx_ring_empty_descs: extended.then_some(stats.tx_ring_empty_descs),
+        })
+    }
+}
@@ -0,0 +1,188 @@
+//! # TX Metadata
+//!
+//! ## Purpose
+//!
+//! This file provides the typed TX metadata of AF_XDP sockets, which lets a transmitted
+//! packet request L4 checksum offload, a TX timestamp or a launch time from the driver.
+//! A UDP sender can then leave the checksum computation to the NIC.
+//!
+//! ## How it works
+//!
+//! When `XdpConfig::tx_metadata_len` is set, the UMEM is registered with a metadata
+//! area of that size in front of every transmitted packet, and TX descriptors point
+//! right after it. `peek_metadata` returns the area of an available descriptor as a
+//! reset `TxMetadata` and marks the descriptor with `XDP_TX_METADATA`, so the kernel
+//! reads the requests when the packet is committed. For multi-buffer packets, only the
+//! metadata of the first descriptor is used.
+//!
+//! ## Main components
+//!
+//! - `TxMetadata`: The layout of `struct xsk_tx_metadata` with typed request setters.
+//! - `impl Socket<_TX>`: Provides `peek_metadata` and `peek_metadata_at`.
+
+use crate::socket::{_TX, RingError, Socket};
+use std::ptr;
+
+/// Requests a TX timestamp, reported in the completion metadata.
+pub const XDP_TXMD_FLAGS_TIMESTAMP: u64 = 1 << 0;
+/// Requests L4 checksum offload.
+pub const XDP_TXMD_FLAGS_CHECKSUM: u64 = 1 << 1;
+/// Requests the packet to be sent at a given time.
+pub const XDP_TXMD_FLAGS_LAUNCH_TIME: u64 = 1 << 2;
+
+/// The TX metadata placed in front of a transmitted packet.
+///
+/// This struct corresponds to `struct xsk_tx_metadata` in the kernel, with the
+/// request variant of its union. Drivers honor only the requests they support.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxMetadata {
+    /// The `XDP_TXMD_FLAGS_*` requests.
+    pub flags: u64,
+    /// The offset from the packet start where checksumming begins.
+    pub csum_start: u16,
+    /// The offset from `csum_start` where the checksum is stored.
+    pub csum_offset: u16,
+    /// Padding of the kernel struct, holding `COMPLETION_PENDING` until the kernel
+    /// writes the completion timestamp over it.
+    _pad: u32,
+    /// The time the packet should be sent at, in nanoseconds of the clock used
+    /// by the NIC.
+    pub launch_time: u64,
+}
+
+/// The marker left in the padding of the request fields, which a TX timestamp only
+/// matches centuries from now.
+const COMPLETION_PENDING: u32 = u32::MAX;
+
+impl Default for TxMetadata {
+    fn default() -> Self {
+        TxMetadata {
+            flags: 0,
+            csum_start: 0,
+            csum_offset: 0,
+            _pad: COMPLETION_PENDING,
+            launch_time: 0,
+        }
+    }
+}
+
+impl TxMetadata {
+    /// Requests the L4 checksum to be computed by the NIC.
+    ///
+    /// The checksum field must hold the checksum of the pseudo-header, like for
+    /// `CHECKSUM_PARTIAL` packets. For UDP, `csum_start` is the offset of the UDP
+    /// header and `csum_offset` is 6; for TCP it is 16.
+    ///
+    /// # Arguments
+    /// * `csum_start` - The offset from the packet start where checksumming begins.
+    /// * `csum_offset` - The offset from `csum_start` where the checksum is stored.
+    pub fn request_checksum(&mut self, csum_start: u16, csum_offset: u16) -> &mut Self {
+        self.flags |= XDP_TXMD_FLAGS_CHECKSUM;
+        self.csum_start = csum_start;
+        self.csum_offset = csum_offset;
+        self
+    }
+
+    /// Requests a TX timestamp for the packet, reported by `completions` once the
+    /// packet is sent.
+    pub fn request_timestamp(&mut self) -> &mut Self {
+        self.flags |= XDP_TXMD_FLAGS_TIMESTAMP;
+        self
+    }
+
+    /// Requests the packet to be sent at `launch_time`, in nanoseconds of the clock
+    /// used by the NIC. Requires Linux 6.15 or newer.
+    pub fn request_launch_time(&mut self, launch_time: u64) -> &mut Self {
+        self.flags |= XDP_TXMD_FLAGS_LAUNCH_TIME;
+        self.launch_time = launch_time;
+        self
+    }
+
+    /// Returns the TX timestamp the kernel writes over the request fields when the
+    /// packet is completed, or `None` if the request fields are still in place.
+    #[inline]
+    pub(crate) fn completion_timestamp(&self) -> Option<u64> {
+        // the completion variant of the kernel union overlaps the checksum request
+        //  and the padding, so the marker is gone once a timestamp is written
+        let timestamp =
+            unsafe { ptr::read((self as *const Self as *const u8).add(8) as *const u64) };
+        (self._pad != COMPLETION_PENDING).then_some(timestamp)
+    }
+}
+
+impl Socket<_TX> {
+    /// Returns the TX metadata of the first available descriptor.
+    ///
+    /// See `peek_metadata_at` for details.
+    #[inline]
+    pub fn peek_metadata(&mut self) -> Result<&mut TxMetadata, RingError> {
+        self.peek_metadata_at(0)
+    }
+
+    /// Returns the TX metadata of the `index`-th available descriptor.
+    ///
+    /// The metadata is reset to its default value and the descriptor is marked with
+    /// `XDP_TX_METADATA`, so the requests set on it are submitted with the packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index in the range of available descriptors.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a mutable reference to the metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RingError::NoMetadata` if no TX metadata area is configured, and
+    /// `RingError::InvalidIndex` if `index` is out of the available range.
+    pub fn peek_metadata_at(&mut self, index: usize) -> Result<&mut TxMetadata, RingError> {
+        if self.layout.tx_metadata_len == 0 {
+            return Err(RingError::NoMetadata);
+        }
+        #[cfg(not(feature = "no_safety_checks"))]
+        if index >= self.available as usize {
+            return Err(RingError::InvalidIndex);
+        }
+        let x_head = self.producer.wrapping_add(index as u32) & self.x_ring.mod_mask;
+        let desc = self.x_ring.mut_desc_at(x_head);
+        desc.options |= libc::XDP_TX_METADATA;
+        let offset = self.layout.resolve(desc.addr) - self.layout.tx_metadata_len as u64;
+        // the metadata area lies within the frame, 8-byte aligned by the layout checks
+        let metadata = unsafe { &mut *(self.frames.add(offset as usize) as *mut TxMetadata) };
+        *metadata = TxMetadata::default();
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_timestamp_requires_kernel_write() {
+        let mut metadata = TxMetadata::default();
+        metadata.request_timestamp().request_checksum(34, 6);
+        assert_eq!(metadata.completion_timestamp(), None);
+
+        // the kernel writes the completion variant over the request fields
+        let timestamp: u64 = 1_700_000_000_123_456_789;
+        unsafe {
+            ptr::write(
+                (&mut metadata as *mut TxMetadata as *mut u8).add(8) as *mut u64,
+                timestamp,
+            )
+        };
+        assert_eq!(metadata.completion_timestamp(), Some(timestamp));
+
+        // a timestamp below 2^32 is reported as well
+        unsafe {
+            ptr::write(
+                (&mut metadata as *mut TxMetadata as *mut u8).add(8) as *mut u64,
+                42,
+            )
+        };
+        assert_eq!(metadata.completion_timestamp(), Some(42));
+    }
+}
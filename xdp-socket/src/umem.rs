@@ -0,0 +1,302 @@
+//! # Shared UMEM
+//!
+//! ## Purpose
+//!
+//! This file defines the `Umem` struct, the userspace memory region holding packet
+//! frames together with the AF_XDP socket it is registered on. A `Umem` can be created
+//! once and bound by several sockets on different queues or interfaces, so a frame
+//! received on one NIC can be placed into the TX ring of another without copying.
+//!
+//! ## How it works
+//!
+//! `Umem::new` allocates the memory region, opens an AF_XDP socket and registers the
+//! region on it with `XDP_UMEM_REG`. The first socket created with the `Umem` reuses
+//! this registering socket. Every following socket opens its own AF_XDP socket and
+//! binds with `XDP_SHARED_UMEM`, pointing `sxdp_shared_umem_fd` to the registering
+//! socket. Each socket has its own Fill and Completion ring pair and takes its frames
+//! from the `FramePool` of the `Umem`, which the application can draw from as well:
+//! `frame_mut` gives access to the data of an allocated frame.
+//!
+//! If the first socket fails to bind, or once it is closed, the registering socket is
+//! replaced by a fresh one registering the same memory, so the next socket can take
+//! it. The frames of a failed or closed socket go back to the pool. A UMEM allocated
+//! by `create_socket` for a single socket is marked private at creation and skips
+//! both steps, as it goes away with its socket.
+//!
+//! The kernel only allows a single Fill/Completion ring pair per interface queue, so
+//! sockets sharing a `Umem` must be bound to distinct queues or interfaces. Bind
+//! options like zero-copy, need-wakeup and multi-buffer are inherited from the first
+//! socket.
+//!
+//! ## Main components
+//!
+//! - `Umem`: The shared memory region, its registering socket and its frame pool.
+
+use crate::create::{XdpConfig, frame_layout, register_umem, setup_umem};
+use crate::frame_pool::FramePool;
+use crate::mmap::OwnedMmap;
+use crate::ring::FrameLayout;
+use std::io;
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd};
+use std::sync::Mutex;
+
+/// A UMEM region that can be shared by several AF_XDP sockets.
+///
+/// Wrap it into an `Arc` and pass it to `create_shared_socket` for every socket
+/// that should use it. The memory is released when the last socket and the `Umem`
+/// itself are dropped.
+pub struct Umem {
+    /// The memory-mapped UMEM region.
+    mmap: OwnedMmap,
+    /// The AF_XDP socket the UMEM is registered on.
+    fd: OwnedFd,
+    /// The geometry of the UMEM frames.
+    layout: FrameLayout,
+    /// The free frames.
+    pool: FramePool,
+    /// Whether the UMEM was allocated for a single socket by `create_socket` or
+    /// `SocketBuilder`, so it goes away with that socket.
+    private: bool,
+    /// The binding of the registering socket.
+    state: Mutex<UmemState>,
+}
+
+/// The mutable part of a `Umem`.
+struct UmemState {
+    /// Whether the registering socket has been taken by a socket.
+    claimed: bool,
+    /// The interface index and queue the registering socket is bound to.
+    bound: Option<(u32, u32)>,
+    /// Whether the registering socket is bound with `XDP_USE_SG`.
+    multi_buffer: bool,
+}
+
+// Umem refers to memory shared with the kernel, the mutable state is behind a mutex
+//  so it can be shared between threads
+unsafe impl Send for Umem {}
+unsafe impl Sync for Umem {}
+
+impl Umem {
+    /// Allocates a UMEM region and registers it with the kernel.
+    ///
+    /// The frame size, frame count, headroom and huge page settings are taken from
+    /// `config`; all other fields are used later by the sockets binding to it.
+    ///
+    /// # Arguments
+    /// * `config` - Optional configuration of the UMEM geometry.
+    ///
+    /// # Returns
+    /// A `Result` containing the new `Umem`, or an `io::Error` on failure.
+    pub fn new(config: Option<XdpConfig>) -> io::Result<Self> {
+        let layout = frame_layout(config.as_ref())?;
+        let (fd, raw_fd) = unsafe {
+            let fd = libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            (OwnedFd::from_raw_fd(fd), fd)
+        };
+        let mmap = setup_umem(raw_fd, &layout, config.as_ref())?;
+        Ok(Umem {
+            mmap,
+            fd,
+            layout,
+            pool: FramePool::new(layout),
+            private: false,
+            state: Mutex::new(UmemState {
+                claimed: false,
+                bound: None,
+                multi_buffer: false,
+            }),
+        })
+    }
+
+    /// Allocates a UMEM region for a single socket, which is never shared.
+    ///
+    /// # Arguments
+    /// * `config` - Optional configuration of the UMEM geometry.
+    pub(crate) fn new_private(config: Option<XdpConfig>) -> io::Result<Self> {
+        let mut umem = Umem::new(config)?;
+        umem.private = true;
+        Ok(umem)
+    }
+
+    /// Returns `true` if the UMEM was allocated for a single socket, so its frames
+    /// need not go back to the pool when the socket is closed.
+    #[inline]
+    pub(crate) fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// Returns the geometry of the UMEM frames.
+    #[inline]
+    pub fn layout(&self) -> FrameLayout {
+        self.layout
+    }
+
+    /// Returns the number of free frames in the pool.
+    pub fn free_frames(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Returns the pool of free frames, to allocate frames held by the application.
+    #[inline]
+    pub fn pool(&self) -> &FramePool {
+        &self.pool
+    }
+
+    /// Returns the packet data area of a frame, to write a frame allocated from the
+    /// pool before passing it to `Socket::send_frame`.
+    ///
+    /// The area starts `FrameLayout::data_offset` bytes into the frame and is
+    /// `FrameLayout::capacity` bytes long.
+    ///
+    /// # Arguments
+    /// * `addr` - An address within the frame, e.g. one returned by `FramePool::alloc`.
+    ///
+    /// # Safety
+    /// The caller must own the frame: it must have been allocated from the pool and
+    /// not be in any ring, and no other reference to its data may exist.
+    ///
+    /// # Panics
+    /// Panics if `addr` is outside the UMEM, unless the `no_safety_checks` feature
+    /// is enabled.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn frame_mut(&self, addr: u64) -> &mut [u8] {
+        #[cfg(not(feature = "no_safety_checks"))]
+        assert!(self.layout.resolve(addr) < self.layout.umem_size() as u64);
+        let data = self.layout.data_addr(addr) as usize;
+        unsafe { std::slice::from_raw_parts_mut(self.frames().add(data), self.layout.capacity()) }
+    }
+
+    /// Returns the raw file descriptor of the socket the UMEM is registered on.
+    #[inline]
+    pub fn raw_fd(&self) -> libc::c_int {
+        self.fd.as_raw_fd()
+    }
+
+    /// Returns a raw pointer to the start of the UMEM frames area.
+    #[inline]
+    pub(crate) fn frames(&self) -> *mut u8 {
+        self.mmap.0 as *mut u8
+    }
+
+    /// Takes the registering socket for the first socket bound to this UMEM.
+    ///
+    /// Returns `Ok(true)` if the caller must use the registering socket and bind it
+    /// without `XDP_SHARED_UMEM`, or `Ok(false)` if it must open its own socket and
+    /// share the UMEM.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the registering socket failed to bind, or if it is
+    /// already bound to the same interface queue.
+    pub(crate) fn claim(&self, if_index: u32, if_queue: u32) -> io::Result<bool> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.claimed {
+            state.claimed = true;
+            return Ok(true);
+        }
+        match state.bound {
+            None => Err(io::Error::other(
+                "UMEM is not bound, the first socket using it has failed",
+            )),
+            Some(bound) if bound == (if_index, if_queue) => Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!(
+                    "UMEM is already bound to interface {if_index} queue {if_queue}, \
+                     shared sockets must use another queue or interface"
+                ),
+            )),
+            Some(_) => Ok(false),
+        }
+    }
+
+    /// Records the interface queue the registering socket has been bound to, and
+    /// whether it is bound with `XDP_USE_SG`.
+    pub(crate) fn set_bound(&self, if_index: u32, if_queue: u32, multi_buffer: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.bound = Some((if_index, if_queue));
+        state.multi_buffer = multi_buffer;
+    }
+
+    /// Returns `true` if the registering socket is bound with `XDP_USE_SG`, which
+    /// sockets sharing the UMEM inherit.
+    pub(crate) fn multi_buffer(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.multi_buffer
+    }
+
+    /// Takes `count` frames from the pool for a socket and returns their base
+    /// addresses.
+    ///
+    /// The frames must be given back with `release` if the socket fails to bind.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if fewer than `count` frames are free.
+    pub(crate) fn reserve(&self, count: usize) -> io::Result<Vec<u64>> {
+        let mut frames = Vec::with_capacity(count);
+        let taken = self.pool.alloc_n(count, &mut frames);
+        if taken < count {
+            frames.into_iter().for_each(|addr| self.pool.free(addr));
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                format!("UMEM has {taken} free frames, {count} requested"),
+            ));
+        }
+        Ok(frames)
+    }
+
+    /// Returns frames of a closed or failed socket to the pool.
+    pub(crate) fn release(&self, frames: &[u64]) {
+        frames.iter().for_each(|&addr| self.pool.free(addr));
+    }
+
+    /// Replaces the registering socket with a fresh one registering the same memory,
+    /// so the next socket created with the UMEM takes it again.
+    ///
+    /// The replaced socket is closed, which releases it once its rings are unmapped.
+    /// Sockets already sharing the UMEM are not affected.
+    ///
+    /// # Errors
+    /// Returns an `io::Error` if the new socket cannot be created or registered; the
+    /// UMEM then stays claimed and no further socket can be created with it.
+    pub(crate) fn reset(&self) -> io::Result<()> {
+        let fd = unsafe {
+            let fd = libc::socket(libc::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            OwnedFd::from_raw_fd(fd)
+        };
+        register_umem(fd.as_raw_fd(), &self.mmap, &self.layout)?;
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        // the raw descriptor number stays the same, the old socket is closed
+        if unsafe { libc::dup3(fd.as_raw_fd(), self.fd.as_raw_fd(), libc::O_CLOEXEC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        state.claimed = false;
+        state.bound = None;
+        state.multi_buffer = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl Umem {
+    /// Creates a UMEM with the given layout, neither backed by memory nor registered
+    /// with the kernel, to test the checks made against a shared UMEM.
+    pub(crate) fn unregistered(layout: FrameLayout) -> Self {
+        Umem {
+            mmap: OwnedMmap(std::ptr::null_mut(), 0),
+            fd: std::fs::File::open("/dev/null").unwrap().into(),
+            layout,
+            pool: FramePool::new(layout),
+            private: false,
+            state: Mutex::new(UmemState {
+                claimed: false,
+                bound: None,
+                multi_buffer: false,
+            }),
+        }
+    }
+}
@@ -1,10 +1,9 @@
 use crate::nettest::suite::vethpair;
 use std::fmt::{Debug, Display};
 use std::future::Future;
-use std::io::{Error, ErrorKind, Result};
+use std::io::{ErrorKind, Result};
 use std::net::Ipv4Addr;
 use std::str::FromStr as _;
-use xdp_util::get_ipv4_address;
 
 pub const DEV_PREFIX: &str = "xdpVeth";
 pub const IP_PREFIX: &str = "192.168.77.";
@@ -41,13 +40,7 @@ impl Display for Host {
 impl Host {
     pub fn new(if_dev: String, ip_str: String) -> Self {
         let ip_addr = Ipv4Addr::from_str(&ip_str).expect("Invalid IP address format");
-        let if_index = get_ipv4_address(None)
-            .unwrap()
-            .iter()
-            .find(|(addr, _)| *addr == ip_addr)
-            .ok_or_else(|| Error::other(format!("Source IP {} not found", ip_addr)))
-            .unwrap()
-            .1;
+        let if_index = xdp_socket::if_index_by_name(&if_dev).unwrap();
         Host {
             if_dev,
             ip_str,